@@ -2410,6 +2410,28 @@ class StatusHTTPHandler(BaseHTTPRequestHandler):
                 self.end_headers()
                 self.wfile.write(json.dumps({"error": str(e)}).encode())
 
+        elif self.path == "/api/overlay/disconnect":
+            # Overlay zamyka się (np. "quit" z zasobnika), więc nic już nie wyświetla
+            content_length = int(self.headers.get("Content-Length", 0))
+            post_data = self.rfile.read(content_length) if content_length else b"{}"
+
+            try:
+                data = json.loads(post_data.decode("utf-8") or "{}")
+                logger.info(f"Overlay disconnected: {data.get('reason', 'unknown')}")
+                self.client_app.overlay_visible = False
+
+                self.send_response(200)
+                self.send_header("Content-type", "application/json")
+                self.send_header("Access-Control-Allow-Origin", "*")
+                self.end_headers()
+                self.wfile.write(json.dumps({"success": True}).encode())
+
+            except Exception as e:
+                self.send_response(400)
+                self.send_header("Content-type", "application/json")
+                self.end_headers()
+                self.wfile.write(json.dumps({"error": str(e)}).encode())
+
         else:
             self.send_response(404)
             self.end_headers()
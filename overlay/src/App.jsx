@@ -1,16 +1,41 @@
-import React, { useState, useEffect } from "react";
+import React, { useState, useEffect, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/tauri";
 import "./style.css"; // Ensure this is importing the updated style.css
+import { applyTheme } from "./theme";
+
+// Used until Rust sends a localized display_status; keyed by base language code
+const FALLBACK_LABELS = {
+  pl: { speaking: "Mówię...", listening: "Słucham...", wakeWord: "Słucham po wake word..." },
+  en: { speaking: "Speaking...", listening: "Listening...", wakeWord: "Listening after wake word..." },
+};
+
+// overlay.position values that put the overlay in a fixed-size corner panel
+const CORNERS = ["top-left", "top-right", "bottom-left", "bottom-right"];
+
+const fallbackLabels = (language) =>
+  FALLBACK_LABELS[(language || "").split(/[-_]/)[0].toLowerCase()] || FALLBACK_LABELS.pl;
 
 const App = () => {
   // const [status, setStatus] = useState('idle'); // Raw status string from backend
   const [text, setText] = useState("");
+  const [fullText, setFullText] = useState(null); // set when Rust cut `text` short
+  const [expanded, setExpanded] = useState(false);
   const [isVisible, setIsVisible] = useState(false);
   const [isListening, setIsListening] = useState(false);
   const [isSpeaking, setIsSpeaking] = useState(false);
   const [wakeWordDetected, setWakeWordDetected] = useState(false);
   const [showBall, setShowBall] = useState(false);
+  const [localizedStatus, setLocalizedStatus] = useState(""); // display_status from Rust (VoiceSettings.language)
+  const [opacity, setOpacity] = useState(1); // overlay.opacity from overlay_settings.json
+  const [textScale, setTextScale] = useState(1); // overlay.text_scale, changed by set_text_scale
+  const [displayMode, setDisplayMode] = useState("full"); // "full" or "compact"
+  const [language, setLanguage] = useState("pl-PL"); // voice.language, changed by set_language
+  const [contentOffset, setContentOffset] = useState({ x: 0, y: 0 }); // set_content_offset, already clamped by Rust
+  const [connected, setConnected] = useState(true); // status-update connection.connected
+  const [fitSetting, setFitSetting] = useState(false); // overlay.fit_to_content, only used when fullscreen
+  const [cornerPanel, setCornerPanel] = useState(false); // overlay.position is a corner
+  const containerRef = useRef(null);
 
   useEffect(() => {
     console.log("[React] App starting, setting up state and listeners");
@@ -28,6 +53,23 @@ const App = () => {
 
     document.addEventListener("keydown", handleKeyDown);
 
+    // Otherwise Rust replaces the page with a "failed to load" message after a while
+    invoke("frontend_ready").catch(console.error);
+
+    invoke("load_settings")
+      .then((settings) => {
+        applyTheme(settings.theme);
+        setOpacity(settings.overlay.opacity);
+        setTextScale(settings.overlay.text_scale);
+        setDisplayMode(settings.overlay.display_mode);
+        setLanguage(settings.voice.language);
+        setFitSetting(settings.overlay.fit_to_content);
+        setCornerPanel(CORNERS.includes(settings.overlay.position));
+      })
+      .catch(console.error);
+
+    invoke("get_content_offset").then(setContentOffset).catch(console.error);
+
     invoke("get_state").then((initialState) => {
       console.log("[React] Initial state received:", initialState);
       // setStatus(initialState.status);
@@ -43,9 +85,12 @@ const App = () => {
       const payload = event.payload;
       // setStatus(payload.status); // The specific booleans are more useful for UI state
       setText(payload.text);
+      setFullText(payload.full_text ?? null);
       setIsListening(payload.is_listening);
       setIsSpeaking(payload.is_speaking);
+      setLocalizedStatus(payload.display_status || "");
       setWakeWordDetected(payload.wake_word_detected); // Visibility logic primarily handled by Rust.
+      setConnected(payload.connection ? payload.connection.connected : true);
       // React focuses on rendering the correct content based on state.
       setIsVisible(
         payload.is_listening ||
@@ -55,11 +100,73 @@ const App = () => {
       );
     });
 
+    // Clear the last content while hidden so it doesn't flash on the next show
+    const unlistenHidden = listen("overlay-hidden", (event) => {
+      console.log("[React] Overlay hidden:", event.payload.reason);
+      setText("");
+      setFullText(null);
+      setLocalizedStatus("");
+      setIsListening(false);
+      setIsSpeaking(false);
+      setWakeWordDetected(false);
+      setIsVisible(false);
+    });
+
+    const unlistenDisplayMode = listen("display-mode", (event) => {
+      setDisplayMode(event.payload);
+    });
+
+    const unlistenTextScale = listen("text-scale", (event) => {
+      setTextScale(event.payload);
+    });
+
+    const unlistenContentOffset = listen("content-offset", (event) => {
+      setContentOffset(event.payload);
+    });
+
+    const unlistenLanguage = listen("language-changed", (event) => {
+      setLanguage(event.payload);
+    });
+
+    const unlistenTheme = listen("theme-changed", (event) => applyTheme(event.payload));
+
+    const unlistenMonitorWarning = listen("monitor-warning", (event) => {
+      console.warn("[React] Monitor detection failed:", event.payload);
+    });
+
     return () => {
       unlisten.then((f) => f());
+      unlistenDisplayMode.then((f) => f());
+      unlistenTextScale.then((f) => f());
+      unlistenContentOffset.then((f) => f());
+      unlistenMonitorWarning.then((f) => f());
+      unlistenLanguage.then((f) => f());
+      unlistenTheme.then((f) => f());
+      unlistenHidden.then((f) => f());
       document.removeEventListener("keydown", handleKeyDown);
     };
   }, []);
+  // With fit_to_content, Rust sizes the window around the content box reported here
+  const fitToContent = fitSetting && !cornerPanel && displayMode !== "compact";
+  const hasContent = showBall || text !== "";
+  useEffect(() => {
+    if (!fitToContent || !containerRef.current) return;
+    const report = () => {
+      const { width, height } = hasContent
+        ? containerRef.current.getBoundingClientRect()
+        : { width: 0, height: 0 };
+      invoke("set_content_size", { width, height }).catch(console.error);
+    };
+    const observer = new ResizeObserver(report);
+    observer.observe(containerRef.current);
+    report();
+    return () => observer.disconnect();
+  }, [fitToContent, hasContent]);
+
+  // New text starts collapsed again
+  useEffect(() => setExpanded(false), [fullText]);
+  const shownText = expanded && fullText ? fullText : text;
+
   // Trigger ball animation when overlay becomes active
   useEffect(() => {
     if (isListening || isSpeaking || wakeWordDetected) {
@@ -73,15 +180,16 @@ const App = () => {
   // Determine current display status string for UI
   let displayStatusText = "";
   let animationClass = "";
+  const labels = fallbackLabels(language);
 
   if (isSpeaking) {
-    displayStatusText = "Mówię...";
+    displayStatusText = localizedStatus || labels.speaking;
     animationClass = "speaking-animation";
   } else if (isListening) {
-    displayStatusText = "Słucham...";
+    displayStatusText = localizedStatus || labels.listening;
     animationClass = "listening-animation";
   } else if (wakeWordDetected) {
-    displayStatusText = "Słucham po wake word..."; // More descriptive for wake word active state
+    displayStatusText = localizedStatus || labels.wakeWord; // More descriptive for wake word active state
     animationClass = "wakeword-animation";
   } // Render content always - Rust manages window visibility
   // React only focuses on displaying the correct content based on state  // Helper function to get dynamic font size class based on text length
@@ -103,7 +211,15 @@ const App = () => {
     return "";
   };
   return (
-    <div className={`overlay-container ${animationClass}`}>
+    <div
+      ref={containerRef}
+      className={`overlay-container ${animationClass} ${displayMode === "compact" ? "compact" : ""} ${connected ? "" : "reconnecting"} ${fitToContent ? "fit-content" : ""}`}
+      style={{
+        opacity,
+        transform: `translate(${contentOffset.x}px, ${contentOffset.y}px)`,
+        "--text-scale": textScale, // font sizes in style.css are multiplied by it
+      }}
+    >
       {/* Gray gradient background when overlay is active */}
       {(isListening || isSpeaking || wakeWordDetected) && (
         <div className="overlay-background"></div>
@@ -120,8 +236,11 @@ const App = () => {
       )}
       {/* Response text with dynamic font size */}
       {text && (
-        <div className={`gaja-response-text ${getTextSizeClass(text)}`}>
-          <p>{text}</p>
+        <div
+          className={`gaja-response-text ${getTextSizeClass(shownText)} ${fullText ? "truncated" : ""}`}
+          onClick={fullText ? () => setExpanded(!expanded) : undefined}
+        >
+          <p>{shownText}</p>
         </div>
       )}
     </div>
@@ -0,0 +1,457 @@
+import React, { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/tauri";
+import { listen } from "@tauri-apps/api/event";
+import { applyTheme } from "./theme";
+
+// Server log lines kept in the viewer
+const MAX_LOG_LINES = 500;
+
+// Minimal settings editor: shows overlay_settings.json and saves it through the Rust commands
+const Settings = () => {
+  const [settingsText, setSettingsText] = useState("");
+  const [message, setMessage] = useState("");
+  const [backupPath, setBackupPath] = useState("");
+  const [bundlePath, setBundlePath] = useState("");
+  const [redactText, setRedactText] = useState(true);
+  const [serverInfo, setServerInfo] = useState(null);
+  const [connectionFailure, setConnectionFailure] = useState(null);
+  const [reconnecting, setReconnecting] = useState(null);
+  const [monitorWarning, setMonitorWarning] = useState("");
+  const [logLines, setLogLines] = useState([]);
+  const [logStreaming, setLogStreaming] = useState(false);
+  const [tuning, setTuning] = useState(false);
+  const [selfTest, setSelfTest] = useState("");
+  const [effectiveConfig, setEffectiveConfig] = useState("");
+  const [previewSensitivity, setPreviewSensitivity] = useState(null);
+  const [wakeConfidence, setWakeConfidence] = useState(null);
+  const [wakeWords, setWakeWords] = useState([]);
+  const [candidateServers, setCandidateServers] = useState([]);
+
+  const loadSettings = () => {
+    invoke("load_settings")
+      .then((settings) => {
+        applyTheme(settings.theme);
+        setSettingsText(JSON.stringify(settings, null, 2));
+        setMessage("");
+      })
+      .catch((e) => setMessage(`Failed to load settings: ${e}`));
+  };
+
+  const saveSettings = () => {
+    let parsed;
+    try {
+      parsed = JSON.parse(settingsText);
+    } catch (e) {
+      setMessage(`Invalid JSON: ${e.message}`);
+      return;
+    }
+    invoke("save_settings", { newSettings: parsed })
+      .then(() => setMessage("Settings saved"))
+      .catch((e) => setMessage(`Failed to save settings: ${e}`));
+  };
+
+  const resetSettings = () => {
+    invoke("reset_settings")
+      .then((settings) => {
+        setSettingsText(JSON.stringify(settings, null, 2));
+        setMessage("Settings reset, previous file kept as overlay_settings.json.bak");
+      })
+      .catch((e) => setMessage(`Failed to reset settings: ${e}`));
+  };
+
+  const exportSettings = () => {
+    invoke("export_settings", { path: backupPath })
+      .then(() => setMessage(`Settings exported to ${backupPath}`))
+      .catch((e) => setMessage(`Failed to export settings: ${e}`));
+  };
+
+  const importSettings = () => {
+    invoke("import_settings", { path: backupPath })
+      .then((settings) => {
+        setSettingsText(JSON.stringify(settings, null, 2));
+        setMessage(`Settings imported from ${backupPath}`);
+      })
+      .catch((e) => setMessage(`Failed to import settings: ${e}`));
+  };
+
+  const exportDiagnostics = () => {
+    setMessage("Writing diagnostics bundle...");
+    invoke("export_diagnostics_bundle", { path: bundlePath, redactText })
+      .then(() => setMessage(`Diagnostics written to ${bundlePath}, attach it to the bug report`))
+      .catch((e) => setMessage(`Failed to export diagnostics: ${e}`));
+  };
+
+  const reconnectNow = () => {
+    invoke("reconnect_now")
+      .then(() => {
+        setConnectionFailure(null);
+        setMessage("Reconnecting...");
+      })
+      .catch((e) => setMessage(`Failed to reconnect: ${e}`));
+  };
+
+  const triggerBriefing = () => {
+    setMessage("Requesting briefing...");
+    invoke("trigger_briefing")
+      .then((text) => setMessage(`Briefing: ${text}`))
+      .catch((e) => setMessage(`Failed to get the briefing: ${e}`));
+  };
+
+  // Fills daily_briefing.location in the editor; nothing is stored until Save
+  const detectLocation = () => {
+    invoke("detect_location")
+      .then((location) => {
+        const parsed = JSON.parse(settingsText);
+        parsed.daily_briefing = { ...parsed.daily_briefing, location };
+        setSettingsText(JSON.stringify(parsed, null, 2));
+        setMessage(`Detected location ${location}. Press Save to keep it.`);
+      })
+      .catch((e) => setMessage(`Failed to detect location: ${e}`));
+  };
+
+  const startLogStream = () => {
+    invoke("start_log_stream")
+      .then(() => {
+        setLogLines([]);
+        setLogStreaming(true);
+      })
+      .catch((e) => setMessage(`Failed to start the log stream: ${e}`));
+  };
+
+  const stopLogStream = () => {
+    invoke("stop_log_stream")
+      .then(() => setLogStreaming(false))
+      .catch((e) => setMessage(`Failed to stop the log stream: ${e}`));
+  };
+
+  // Plain text so it can be pasted into a bug report as is
+  const runSelfTest = () => {
+    setSelfTest("Running self-test...");
+    invoke("run_self_test")
+      .then((report) => {
+        const lines = report.checks.map((check) => `[${check.result}] ${check.name}: ${check.detail}`);
+        setSelfTest([`Gaja overlay ${report.overlay_version} on ${report.os}`, ...lines].join("\n"));
+      })
+      .catch((e) => setSelfTest(`Self-test failed to run: ${e}`));
+  };
+
+  // One "key = value (source)" line per setting, to see what's overridden and from where
+  const showEffectiveConfig = () => {
+    invoke("get_effective_config")
+      .then((config) =>
+        setEffectiveConfig(
+          config.map((entry) => `${entry.key} = ${JSON.stringify(entry.value)} (${entry.source})`).join("\n"),
+        ),
+      )
+      .catch((e) => setEffectiveConfig(`Failed to get the effective configuration: ${e}`));
+  };
+
+  const findServers = () => {
+    setMessage("Looking for clients...");
+    invoke("list_candidate_servers")
+      .then((servers) => {
+        setCandidateServers(servers);
+        setMessage("");
+      })
+      .catch((e) => setMessage(`Failed to look for clients: ${e}`));
+  };
+
+  // null goes back to trying the configured ports in order
+  const useServerPort = (port) => {
+    invoke("set_active_port", { port })
+      .then(() => setMessage(port === null ? "Using the first client that answers" : `Connecting to port ${port}`))
+      .catch((e) => setMessage(`Failed to set the port: ${e}`));
+  };
+
+  const startTuning = () => {
+    invoke("start_sensitivity_tuning")
+      .then(() => {
+        setTuning(true);
+        setMessage("Say the wake word to see its detection score");
+      })
+      .catch((e) => setMessage(`Failed to start tuning: ${e}`));
+  };
+
+  const stopTuning = () => {
+    invoke("stop_sensitivity_tuning")
+      .catch((e) => setMessage(`Failed to stop tuning: ${e}`))
+      .finally(() => {
+        setTuning(false);
+        setWakeConfidence(null);
+        setPreviewSensitivity(null);
+      });
+  };
+
+  const previewSensitivityValue = (value) => {
+    setPreviewSensitivity(value);
+    invoke("set_sensitivity_preview", { value }).catch((e) => setMessage(`Failed to preview sensitivity: ${e}`));
+  };
+
+  // Puts the previewed value in the editor; it is saved with the rest of the settings
+  const keepSensitivity = () => {
+    const parsed = JSON.parse(settingsText);
+    parsed.voice = { ...parsed.voice, sensitivity: previewSensitivity };
+    setSettingsText(JSON.stringify(parsed, null, 2));
+    setMessage(`Sensitivity ${previewSensitivity} put in the editor. Press Save to keep it.`);
+  };
+
+  const currentWakeWord = () => {
+    try {
+      return JSON.parse(settingsText).voice?.wake_word ?? "";
+    } catch {
+      return "";
+    }
+  };
+
+  // Puts the chosen wake word in the editor; it is saved with the rest of the settings
+  const chooseWakeWord = (wakeWord) => {
+    let parsed;
+    try {
+      parsed = JSON.parse(settingsText);
+    } catch (e) {
+      setMessage(`Invalid JSON: ${e.message}`);
+      return;
+    }
+    parsed.voice = { ...parsed.voice, wake_word: wakeWord };
+    setSettingsText(JSON.stringify(parsed, null, 2));
+    setMessage(`Wake word "${wakeWord}" put in the editor. Press Save to keep it.`);
+  };
+
+  useEffect(() => {
+    const unlisten = listen("wake-confidence", (event) => {
+      setWakeConfidence(event.payload.confidence);
+      setPreviewSensitivity((current) => (current === null ? event.payload.sensitivity : current));
+    });
+    // Closing the window ends tuning in Rust
+    const unlistenStopped = listen("tuning-stopped", () => {
+      setTuning(false);
+      setWakeConfidence(null);
+      setPreviewSensitivity(null);
+    });
+    return () => {
+      unlisten.then((f) => f());
+      unlistenStopped.then((f) => f());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlistenLog = listen("server-log", (event) =>
+      setLogLines((lines) => [...lines, event.payload].slice(-MAX_LOG_LINES)),
+    );
+    const unlistenLogStopped = listen("server-log-stopped", (event) => {
+      setLogStreaming(false);
+      setMessage(event.payload);
+    });
+    return () => {
+      unlistenLog.then((f) => f());
+      unlistenLogStopped.then((f) => f());
+      invoke("stop_log_stream").catch(console.error);
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen("connection-failed", (event) => setConnectionFailure(event.payload));
+    const unlistenReconnecting = listen("reconnecting", (event) => setReconnecting(event.payload));
+    const unlistenConnected = listen("connected", () => {
+      setReconnecting(null);
+      setConnectionFailure(null);
+    });
+    const unlistenMonitor = listen("monitor-warning", (event) => setMonitorWarning(event.payload));
+    const unlistenTheme = listen("theme-changed", (event) => applyTheme(event.payload));
+    return () => {
+      unlisten.then((f) => f());
+      unlistenReconnecting.then((f) => f());
+      unlistenConnected.then((f) => f());
+      unlistenMonitor.then((f) => f());
+      unlistenTheme.then((f) => f());
+    };
+  }, []);
+
+  useEffect(() => {
+    // Otherwise Rust replaces the page with a "failed to load" message after a while
+    invoke("frontend_ready").catch(console.error);
+    loadSettings();
+    invoke("get_diagnostics")
+      .then((diagnostics) => setMonitorWarning(diagnostics.monitor_warning || ""))
+      .catch(console.error);
+    invoke("get_server_info")
+      .then(setServerInfo)
+      .catch((e) => console.log("[React] Server info not available:", e));
+    invoke("get_available_wake_words")
+      .then(setWakeWords)
+      .catch((e) => console.log("[React] Wake words not available:", e));
+  }, []);
+
+  // Only the major version has to match
+  const majorVersion = (version) => (version || "").split(".")[0];
+  const versionMismatch =
+    serverInfo &&
+    serverInfo.server.version &&
+    majorVersion(serverInfo.server.version) !== majorVersion(serverInfo.overlay_version);
+
+  return (
+    <div className="settings-container">
+      <h1>Gaja Overlay Settings</h1>
+      <textarea
+        className="settings-editor"
+        spellCheck={false}
+        value={settingsText}
+        onChange={(e) => setSettingsText(e.target.value)}
+      />
+      <div className="settings-actions">
+        <button onClick={loadSettings}>Reload</button>
+        <button onClick={saveSettings}>Save</button>
+        <button onClick={resetSettings}>Reset to defaults</button>
+        <button onClick={triggerBriefing}>Briefing now</button>
+        <button onClick={detectLocation}>Detect location</button>
+        <button onClick={runSelfTest}>Self-test</button>
+        <button onClick={showEffectiveConfig}>Effective config</button>
+        <button onClick={findServers}>Find clients</button>
+      </div>
+      <div className="settings-actions">
+        <input
+          className="settings-path"
+          placeholder="Backup file path"
+          value={backupPath}
+          onChange={(e) => setBackupPath(e.target.value)}
+        />
+        <button onClick={exportSettings} disabled={!backupPath}>
+          Export
+        </button>
+        <button onClick={importSettings} disabled={!backupPath}>
+          Import
+        </button>
+      </div>
+      <div className="settings-actions">
+        <input
+          className="settings-path"
+          placeholder="Diagnostics .zip path"
+          value={bundlePath}
+          onChange={(e) => setBundlePath(e.target.value)}
+        />
+        <label>
+          <input type="checkbox" checked={redactText} onChange={(e) => setRedactText(e.target.checked)} /> Leave out
+          what was said
+        </label>
+        <button onClick={exportDiagnostics} disabled={!bundlePath}>
+          Export diagnostics
+        </button>
+      </div>
+      {connectionFailure && (
+        <div className="settings-warning">
+          {connectionFailure.unexpected_response?.auth_required ? (
+            <p>
+              The client at {connectionFailure.unexpected_response.url} requires authentication (status{" "}
+              {connectionFailure.unexpected_response.status}).
+            </p>
+          ) : connectionFailure.unexpected_response ? (
+            <p>
+              Something answered at {connectionFailure.unexpected_response.url}
+              {connectionFailure.unexpected_response.redirected_to &&
+                ` (redirected to ${connectionFailure.unexpected_response.redirected_to})`}{" "}
+              with status{" "}
+              {connectionFailure.unexpected_response.status}, but it is not the Gaja client. Check for a
+              proxy or another program on that port. Response:{" "}
+              <code>{connectionFailure.unexpected_response.body_snippet || "(empty)"}</code>
+            </p>
+          ) : (
+            <p>
+              Gaja client is not running (tried ports {connectionFailure.tried_ports.join(", ")}).
+              {connectionFailure.last_error && ` Last error: ${connectionFailure.last_error}`}
+            </p>
+          )}
+          {reconnecting && (
+            <p>
+              {reconnecting.remaining_ms > 0
+                ? `Retrying in ${Math.ceil(reconnecting.remaining_ms / 1000)}s (attempt ${reconnecting.attempt + 1})...`
+                : "Retrying now..."}
+            </p>
+          )}
+          <button onClick={reconnectNow}>Retry now</button>
+        </div>
+      )}
+      {monitorWarning && (
+        <div className="settings-warning">
+          <p>Monitor detection failed: {monitorWarning}. The overlay may not be where you expect.</p>
+        </div>
+      )}
+      {serverInfo && (
+        <p className="settings-message">
+          Client version {serverInfo.server.version || "unknown"}, overlay version{" "}
+          {serverInfo.overlay_version}
+          {versionMismatch && " - versions don't match, some features may not work"}
+        </p>
+      )}
+      {message && <p className="settings-message">{message}</p>}
+      {selfTest && <pre className="settings-log">{selfTest}</pre>}
+      {effectiveConfig && <pre className="settings-log">{effectiveConfig}</pre>}
+      {candidateServers.length > 0 && (
+        <div className="settings-actions">
+          {candidateServers.map((server) => (
+            <p key={server.port} className="settings-message">
+              Port {server.port}:{" "}
+              {server.base_url
+                ? `${server.status || "answering"}, version ${server.version || "unknown"}${server.active ? " (connected)" : ""}`
+                : `no client (${server.error})`}{" "}
+              {server.base_url && <button onClick={() => useServerPort(server.port)}>Use</button>}
+            </p>
+          ))}
+          <button onClick={() => useServerPort(null)}>Automatic</button>
+        </div>
+      )}
+      <div className="settings-actions">
+        <label>
+          Wake word{" "}
+          <select value={currentWakeWord()} onChange={(e) => chooseWakeWord(e.target.value)}>
+            {!wakeWords.includes(currentWakeWord()) && (
+              <option value={currentWakeWord()}>
+                {currentWakeWord() || "(none)"}
+                {wakeWords.length > 0 && " (not supported)"}
+              </option>
+            )}
+            {wakeWords.map((wakeWord) => (
+              <option key={wakeWord} value={wakeWord}>
+                {wakeWord}
+              </option>
+            ))}
+          </select>
+        </label>
+      </div>
+      <div className="settings-actions">
+        <button onClick={startTuning} disabled={tuning}>
+          Tune sensitivity
+        </button>
+        {tuning && (
+          <>
+            <input
+              type="range"
+              min="0"
+              max="1"
+              step="0.05"
+              value={previewSensitivity ?? 0}
+              onChange={(e) => previewSensitivityValue(Number(e.target.value))}
+            />
+            <span className="settings-message">
+              Sensitivity {previewSensitivity ?? "-"}, last score {wakeConfidence === null ? "-" : wakeConfidence.toFixed(2)}
+            </span>
+            <button onClick={keepSensitivity} disabled={previewSensitivity === null}>
+              Use this value
+            </button>
+            <button onClick={stopTuning}>Stop tuning</button>
+          </>
+        )}
+      </div>
+      <div className="settings-actions">
+        <button onClick={startLogStream} disabled={logStreaming}>
+          Stream server log
+        </button>
+        <button onClick={stopLogStream} disabled={!logStreaming}>
+          Stop
+        </button>
+      </div>
+      {logLines.length > 0 && <pre className="settings-log">{logLines.join("\n")}</pre>}
+    </div>
+  );
+};
+
+export default Settings;
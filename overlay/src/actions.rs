@@ -0,0 +1,88 @@
+// One-shot actions the client can request through the `action` field of a status payload
+
+use crate::status::StatusPayload;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Open an http(s) link in the default browser
+    OpenUrl(String),
+    /// Show a desktop notification
+    Notify { title: String, body: String },
+}
+
+// Used when a "notify" action has no title
+const DEFAULT_NOTIFICATION_TITLE: &str = "Gaja";
+
+impl Action {
+    /// None if the payload has no action, an error if the action is unknown or invalid.
+    pub fn from_payload(payload: &StatusPayload) -> Option<Result<Action, String>> {
+        let action = payload.action.as_deref()?;
+        Some(match action {
+            "open_url" => payload
+                .url
+                .as_deref()
+                .ok_or_else(|| "open_url action without a url".to_string())
+                .and_then(checked_url)
+                .map(Action::OpenUrl),
+            "notify" => match payload.body.as_deref().filter(|body| !body.trim().is_empty()) {
+                Some(body) => Ok(Action::Notify {
+                    title: payload.title.clone().unwrap_or_else(|| DEFAULT_NOTIFICATION_TITLE.to_string()),
+                    body: body.to_string(),
+                }),
+                None => Err("notify action without a body".to_string()),
+            },
+            other => Err(format!("Unknown action '{}'", other)),
+        })
+    }
+}
+
+// Only web links are opened, so a payload can't launch other protocol handlers
+fn checked_url(url: &str) -> Result<String, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid url '{}': {}", url, e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed.to_string()),
+        scheme => Err(format!("Refusing to open '{}' url '{}'", scheme, url)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: serde_json::Value) -> Option<Result<Action, String>> {
+        Action::from_payload(&StatusPayload::parse(&data).unwrap())
+    }
+
+    #[test]
+    fn open_url_accepts_only_web_links() {
+        assert_eq!(
+            parse(serde_json::json!({"action": "open_url", "url": "https://example.com/article"})),
+            Some(Ok(Action::OpenUrl("https://example.com/article".to_string())))
+        );
+        assert!(parse(serde_json::json!({"action": "open_url", "url": "http://localhost:5000/"})).unwrap().is_ok());
+
+        for url in ["file:///C:/Windows/System32/calc.exe", "javascript:alert(1)", "ms-settings:", "not a url"] {
+            assert!(parse(serde_json::json!({"action": "open_url", "url": url})).unwrap().is_err(), "{}", url);
+        }
+        assert!(parse(serde_json::json!({"action": "open_url"})).unwrap().is_err());
+    }
+
+    #[test]
+    fn notify_needs_a_body() {
+        assert_eq!(
+            parse(serde_json::json!({"action": "notify", "title": "Przypomnienie", "body": "Spotkanie o 15:00"})),
+            Some(Ok(Action::Notify { title: "Przypomnienie".to_string(), body: "Spotkanie o 15:00".to_string() }))
+        );
+        assert_eq!(
+            parse(serde_json::json!({"action": "notify", "body": "Spotkanie o 15:00"})),
+            Some(Ok(Action::Notify { title: "Gaja".to_string(), body: "Spotkanie o 15:00".to_string() }))
+        );
+        assert!(parse(serde_json::json!({"action": "notify", "title": "Przypomnienie"})).unwrap().is_err());
+    }
+
+    #[test]
+    fn payloads_without_an_action_are_not_actions() {
+        assert_eq!(parse(serde_json::json!({"text": "https://example.com"})), None);
+        assert!(parse(serde_json::json!({"action": "format_disk"})).unwrap().is_err());
+    }
+}
@@ -0,0 +1,499 @@
+// Audio helpers for the settings UI (microphone level meter).
+// Real device access is only compiled in with the `audio` feature (cpal).
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::Window;
+
+#[cfg(feature = "audio")]
+use std::sync::mpsc;
+#[cfg(feature = "audio")]
+use std::thread::JoinHandle;
+#[cfg(feature = "audio")]
+use std::time::Duration;
+
+// How often the "mic-level" event is emitted while monitoring
+#[cfg(feature = "audio")]
+const MIC_LEVEL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Length of a device test (capture window or test tone)
+#[cfg(feature = "audio")]
+const DEVICE_TEST_DURATION: Duration = Duration::from_millis(1500);
+
+// RMS level above which captured audio counts as non-silent
+#[cfg(feature = "audio")]
+const SILENCE_THRESHOLD: f32 = 0.01;
+
+#[cfg(feature = "audio")]
+pub struct MicMonitor {
+    stop_tx: mpsc::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+    window_label: String,
+}
+
+#[cfg(feature = "audio")]
+impl MicMonitor {
+    fn stop(mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(thread) = self.thread.take() {
+            // The stream is dropped on the monitor thread, which releases the device
+            let _ = thread.join();
+        }
+    }
+
+    fn started_from(&self, window_label: &str) -> bool {
+        self.window_label == window_label
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+pub struct MicMonitor;
+
+#[cfg(not(feature = "audio"))]
+impl MicMonitor {
+    fn stop(self) {}
+
+    fn started_from(&self, _window_label: &str) -> bool {
+        false
+    }
+}
+
+pub type MicMonitorState = Mutex<Option<MicMonitor>>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDevice {
+    /// Identifier to pass back to audio commands (the device name reported by the backend)
+    id: String,
+    name: String,
+    is_default: bool,
+}
+
+/// Result of `get_audio_devices`.
+///
+/// When the overlay is built without the `audio` feature, `audio_supported` is false,
+/// both device lists are empty and `message` explains why, so the UI can tell the user
+/// instead of offering devices that don't exist.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceList {
+    audio_supported: bool,
+    input_devices: Vec<AudioDevice>,
+    output_devices: Vec<AudioDevice>,
+    message: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_audio_devices() -> Result<AudioDeviceList, String> {
+    #[cfg(feature = "audio")]
+    {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let host = cpal::default_host();
+        let default_input = host.default_input_device().and_then(|d| d.name().ok());
+        let default_output = host.default_output_device().and_then(|d| d.name().ok());
+
+        let to_list = |devices: Vec<cpal::Device>, default_name: &Option<String>| {
+            devices
+                .iter()
+                .filter_map(|d| d.name().ok())
+                .map(|name| AudioDevice {
+                    id: name.clone(),
+                    is_default: default_name.as_deref() == Some(name.as_str()),
+                    name,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let inputs = host
+            .input_devices()
+            .map_err(|e| format!("Failed to list input devices: {}", e))?
+            .collect::<Vec<_>>();
+        let outputs = host
+            .output_devices()
+            .map_err(|e| format!("Failed to list output devices: {}", e))?
+            .collect::<Vec<_>>();
+
+        Ok(AudioDeviceList {
+            audio_supported: true,
+            input_devices: to_list(inputs, &default_input),
+            output_devices: to_list(outputs, &default_output),
+            message: None,
+        })
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        Ok(AudioDeviceList {
+            audio_supported: false,
+            input_devices: Vec::new(),
+            output_devices: Vec::new(),
+            message: Some("This build was compiled without audio support (the `audio` feature is disabled)".to_string()),
+        })
+    }
+}
+
+/// Checks that `device_id` (as returned by `get_audio_devices`) names an input or output
+/// device that is currently available.
+pub fn resolve_audio_device(device_id: &str, is_input: bool) -> Result<(), String> {
+    #[cfg(feature = "audio")]
+    {
+        find_device(&cpal::default_host(), device_id, is_input).map(|_| ())
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        let _ = (device_id, is_input);
+        Err("Audio support is not available in this build".to_string())
+    }
+}
+
+/// Self-test: the default input and output devices, which the client uses unless configured
+/// otherwise, can be found. None when the build has no audio support.
+pub fn check_default_devices() -> Option<Result<String, String>> {
+    #[cfg(feature = "audio")]
+    {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let host = cpal::default_host();
+        let name = |device: Option<cpal::Device>, kind: &str| {
+            device
+                .ok_or_else(|| format!("No default {} device", kind))
+                .and_then(|d| d.name().map_err(|e| format!("Default {} device has no name: {}", kind, e)))
+        };
+        Some(
+            name(host.default_input_device(), "input")
+                .and_then(|input| name(host.default_output_device(), "output").map(|output| (input, output)))
+                .map(|(input, output)| format!("input '{}', output '{}' ({:?})", input, output, host.id())),
+        )
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        None
+    }
+}
+
+/// Result of `test_audio_device`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceTest {
+    success: bool,
+    error: Option<String>,
+    /// Input devices only: whether anything louder than silence was captured
+    signal_detected: Option<bool>,
+}
+
+/// Opens the device with `device_id` (as returned by `get_audio_devices`) and checks it works:
+/// input devices capture for a moment, output devices play a short test tone.
+#[tauri::command]
+pub async fn test_audio_device(device_id: String, kind: String) -> Result<AudioDeviceTest, String> {
+    let is_input = match kind.as_str() {
+        "input" => true,
+        "output" => false,
+        other => return Err(format!("Unknown device kind '{}', expected 'input' or 'output'", other)),
+    };
+
+    #[cfg(feature = "audio")]
+    {
+        // cpal streams are not Send, so the whole test runs on a blocking thread
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            if is_input {
+                capture_test(&device_id).map(Some)
+            } else {
+                play_test_tone(&device_id).map(|_| None)
+            }
+        })
+        .await
+        .map_err(|e| format!("Audio test thread failed: {}", e))?;
+
+        Ok(match result {
+            Ok(signal_detected) => AudioDeviceTest { success: true, error: None, signal_detected },
+            Err(e) => {
+                eprintln!("[Rust] Audio device test failed: {}", e);
+                AudioDeviceTest { success: false, error: Some(e), signal_detected: None }
+            }
+        })
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        let _ = (device_id, is_input);
+        Err("Audio support is not available in this build".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn start_mic_monitor(
+    window: Window,
+    device_name: Option<String>,
+    monitor: tauri::State<'_, MicMonitorState>,
+    settings: tauri::State<'_, crate::settings::SharedSettings>
+) -> Result<(), String> {
+    #[cfg(feature = "audio")]
+    {
+        // Without an explicit device, the one the client is configured with (empty for the default)
+        let device_name = device_name.or_else(|| {
+            let configured = settings.lock().unwrap().audio.input_device.clone();
+            (!configured.is_empty()).then_some(configured)
+        });
+        let previous = monitor.lock().unwrap().take();
+        let label = window.label().to_string();
+
+        // Opening the device and joining monitor threads block, so none of it runs on the
+        // main thread or under the monitor lock
+        let started = tauri::async_runtime::spawn_blocking(move || {
+            if let Some(previous) = previous {
+                previous.stop();
+            }
+
+            let (stop_tx, stop_rx) = mpsc::channel::<()>();
+            let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+
+            // cpal streams are not Send, so the stream lives entirely on this thread
+            let thread = std::thread::spawn(move || {
+                run_mic_monitor(window, device_name, stop_rx, ready_tx);
+            });
+
+            match ready_rx.recv() {
+                Ok(Ok(())) => Ok(MicMonitor { stop_tx, thread: Some(thread), window_label: label }),
+                Ok(Err(e)) => {
+                    let _ = thread.join();
+                    Err(e)
+                }
+                Err(_) => {
+                    let _ = thread.join();
+                    Err("Microphone monitor thread exited unexpectedly".to_string())
+                }
+            }
+        })
+        .await
+        .map_err(|e| format!("Microphone monitor thread failed: {}", e))??;
+
+        println!("[Rust] Microphone monitor started for window '{}'", started.window_label);
+        // Another start may have finished while this one was opening the device
+        if let Some(replaced) = monitor.lock().unwrap().replace(started) {
+            tauri::async_runtime::spawn_blocking(move || replaced.stop());
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        let _ = (window, device_name, monitor, settings);
+        Err("Audio support is not available in this build".to_string())
+    }
+}
+
+#[tauri::command]
+pub fn stop_mic_monitor(monitor: tauri::State<'_, MicMonitorState>) -> Result<(), String> {
+    stop_monitor(&monitor);
+    Ok(())
+}
+
+fn stop_monitor(monitor: &MicMonitorState) {
+    let previous = monitor.lock().unwrap().take();
+    if let Some(previous) = previous {
+        println!("[Rust] Stopping microphone monitor");
+        previous.stop();
+    }
+}
+
+/// Stops the monitor if it was started from the window that is going away.
+pub fn stop_mic_monitor_for_window(monitor: &MicMonitorState, window_label: &str) {
+    let started_here = monitor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|m| m.started_from(window_label))
+        .unwrap_or(false);
+    if started_here {
+        stop_monitor(monitor);
+    }
+}
+
+#[cfg(feature = "audio")]
+fn run_mic_monitor(
+    window: Window,
+    device_name: Option<String>,
+    stop_rx: mpsc::Receiver<()>,
+    ready_tx: mpsc::Sender<Result<(), String>>,
+) {
+    use cpal::traits::StreamTrait;
+    use std::sync::Arc;
+
+    let level = Arc::new(Mutex::new(0.0f32));
+    let stream = match build_level_stream(device_name.as_deref(), level.clone()) {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = ready_tx.send(Err(e));
+            return;
+        }
+    };
+    if let Err(e) = stream.play() {
+        let _ = ready_tx.send(Err(format!("Failed to start input stream: {}", e)));
+        return;
+    }
+    let _ = ready_tx.send(Ok(()));
+
+    // Emit the loudest buffer seen since the last tick until asked to stop
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(MIC_LEVEL_INTERVAL) {
+        let current = {
+            let mut level_guard = level.lock().unwrap();
+            std::mem::replace(&mut *level_guard, 0.0)
+        };
+        if let Err(e) = window.emit("mic-level", current) {
+            eprintln!("[Rust] Failed to emit mic-level: {}", e);
+        }
+    }
+
+    drop(stream);
+    println!("[Rust] Microphone monitor stopped, input device released");
+}
+
+#[cfg(feature = "audio")]
+fn build_level_stream(
+    device_name: Option<&str>,
+    level: std::sync::Arc<Mutex<f32>>,
+) -> Result<cpal::Stream, String> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+    use cpal::SampleFormat;
+
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => find_device(&host, name, true)?,
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No default input device available".to_string())?,
+    };
+
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get input config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+
+    match sample_format {
+        SampleFormat::F32 => build_typed_stream::<f32>(&device, &config, level),
+        SampleFormat::I16 => build_typed_stream::<i16>(&device, &config, level),
+        SampleFormat::U16 => build_typed_stream::<u16>(&device, &config, level),
+        other => Err(format!("Unsupported sample format: {:?}", other)),
+    }
+}
+
+#[cfg(feature = "audio")]
+fn build_typed_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    level: std::sync::Arc<Mutex<f32>>,
+) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    use cpal::traits::DeviceTrait;
+
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                if data.is_empty() {
+                    return;
+                }
+                let sum_squares: f32 = data
+                    .iter()
+                    .map(|s| {
+                        let v = s.to_sample::<f32>();
+                        v * v
+                    })
+                    .sum();
+                let rms = (sum_squares / data.len() as f32).sqrt().clamp(0.0, 1.0);
+                let mut level_guard = level.lock().unwrap();
+                if rms > *level_guard {
+                    *level_guard = rms;
+                }
+            },
+            |e| eprintln!("[Rust] Input stream error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open input stream: {}", e))
+}
+
+#[cfg(feature = "audio")]
+fn find_device(host: &cpal::Host, name: &str, is_input: bool) -> Result<cpal::Device, String> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    let devices = if is_input { host.input_devices() } else { host.output_devices() };
+    let kind = if is_input { "Input" } else { "Output" };
+    devices
+        .map_err(|e| format!("Failed to list {} devices: {}", kind.to_lowercase(), e))?
+        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+        .ok_or_else(|| format!("{} device '{}' not found", kind, name))
+}
+
+// Captures from the input device for DEVICE_TEST_DURATION, returns whether any signal was heard
+#[cfg(feature = "audio")]
+fn capture_test(device_name: &str) -> Result<bool, String> {
+    use cpal::traits::StreamTrait;
+    use std::sync::Arc;
+
+    let level = Arc::new(Mutex::new(0.0f32));
+    let stream = build_level_stream(Some(device_name), level.clone())?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start input stream: {}", e))?;
+    std::thread::sleep(DEVICE_TEST_DURATION);
+    drop(stream);
+
+    let peak = *level.lock().unwrap();
+    println!("[Rust] Input device '{}' test finished, peak level {:.3}", device_name, peak);
+    Ok(peak > SILENCE_THRESHOLD)
+}
+
+// Plays a quiet 440 Hz tone on the output device for DEVICE_TEST_DURATION
+#[cfg(feature = "audio")]
+fn play_test_tone(device_name: &str) -> Result<(), String> {
+    use cpal::traits::{DeviceTrait, StreamTrait};
+    use cpal::SampleFormat;
+
+    let device = find_device(&cpal::default_host(), device_name, false)?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_tone_stream::<f32>(&device, &config),
+        SampleFormat::I16 => build_tone_stream::<i16>(&device, &config),
+        SampleFormat::U16 => build_tone_stream::<u16>(&device, &config),
+        other => Err(format!("Unsupported sample format: {:?}", other)),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start output stream: {}", e))?;
+    std::thread::sleep(DEVICE_TEST_DURATION);
+    drop(stream);
+
+    println!("[Rust] Output device '{}' test tone played", device_name);
+    Ok(())
+}
+
+#[cfg(feature = "audio")]
+fn build_tone_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    use cpal::traits::DeviceTrait;
+
+    let channels = config.channels.max(1) as usize;
+    let step = 440.0 * 2.0 * std::f32::consts::PI / config.sample_rate.0 as f32;
+    let mut phase = 0.0f32;
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    let value = T::from_sample(phase.sin() * 0.2);
+                    frame.iter_mut().for_each(|sample| *sample = value);
+                    phase = (phase + step) % (2.0 * std::f32::consts::PI);
+                }
+            },
+            |e| eprintln!("[Rust] Output stream error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open output stream: {}", e))
+}
@@ -0,0 +1,170 @@
+// Daily briefing schedule: when the next briefing is due in local time
+
+use chrono::{DateTime, Duration, LocalResult, NaiveTime, TimeZone};
+
+// How late a briefing may still fire, e.g. when the computer wakes from sleep after the
+// briefing time; later than this it is skipped until the next day
+const MAX_LATENESS_MINUTES: i64 = 60;
+
+// A planned briefing further away than this means the clock was set back (a DST day is 25h)
+const MAX_PLANNED_AHEAD_HOURS: i64 = 26;
+
+/// Parses a "HH:MM" briefing time. "24:00" is accepted as midnight.
+pub fn parse_briefing_time(value: &str) -> Result<NaiveTime, String> {
+    let invalid = || format!("\"{}\" is not a valid time, expected HH:MM", value);
+    let (hours, minutes) = value.trim().split_once(':').ok_or_else(invalid)?;
+    let two_digits = |part: &str| part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit());
+    if !two_digits(hours) || !two_digits(minutes) {
+        return Err(invalid());
+    }
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if (hours, minutes) == (24, 0) {
+        // End of the day, the same moment as midnight
+        return Ok(NaiveTime::MIN);
+    }
+    NaiveTime::from_hms_opt(hours, minutes, 0).ok_or_else(invalid)
+}
+
+/// What the scheduler should do about a briefing planned for `fire_at`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BriefingDue {
+    /// Not yet; check again after at most this long
+    Wait(Duration),
+    Fire,
+    /// The clock passed the briefing time too long ago (sleep, clock moved forward)
+    Missed,
+    /// The clock was set back, so the plan has to be recomputed
+    Reschedule,
+}
+
+pub fn briefing_due<Tz: TimeZone>(now: &DateTime<Tz>, fire_at: &DateTime<Tz>) -> BriefingDue {
+    let remaining = fire_at.clone().signed_duration_since(now);
+    if remaining > Duration::hours(MAX_PLANNED_AHEAD_HOURS) {
+        BriefingDue::Reschedule
+    } else if remaining > Duration::zero() {
+        BriefingDue::Wait(remaining)
+    } else if -remaining > Duration::minutes(MAX_LATENESS_MINUTES) {
+        BriefingDue::Missed
+    } else {
+        BriefingDue::Fire
+    }
+}
+
+/// "City,CC" from an ip-api.com response (`?fields=status,message,city,countryCode`).
+pub fn location_from_geolocation(response: &serde_json::Value) -> Result<String, String> {
+    let field = |name: &str| response.get(name).and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+    if field("status") != Some("success") {
+        return Err(format!("Location lookup failed: {}", field("message").unwrap_or("no reason given")));
+    }
+    match (field("city"), field("countryCode")) {
+        (Some(city), Some(country)) => Ok(format!("{},{}", city, country)),
+        (Some(city), None) => Ok(city.to_string()),
+        _ => Err("Location lookup returned no city".to_string()),
+    }
+}
+
+/// The first moment after `now` at which the local clock shows `time`.
+///
+/// On a DST change, an hour that happens twice fires the first time, and a time that is
+/// skipped fires as soon as the clock jumps past it.
+pub fn next_briefing<Tz: TimeZone>(now: &DateTime<Tz>, time: NaiveTime) -> DateTime<Tz> {
+    let timezone = now.timezone();
+    let mut date = now.date_naive();
+    loop {
+        let local = date.and_time(time);
+        let candidate = match timezone.from_local_datetime(&local) {
+            LocalResult::Single(at) => Some(at),
+            LocalResult::Ambiguous(earliest, _) => Some(earliest),
+            // In a DST gap; the gap is at most a couple of hours on any real timezone
+            LocalResult::None => (1..=3)
+                .map(|hours| timezone.from_local_datetime(&(local + Duration::hours(hours))))
+                .find_map(|result| result.earliest()),
+        };
+        match candidate {
+            Some(at) if at > *now => return at,
+            _ => date = date.succ_opt().expect("date out of range"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn at(timezone: &FixedOffset, date: (i32, u32, u32), time: (u32, u32)) -> DateTime<FixedOffset> {
+        timezone.with_ymd_and_hms(date.0, date.1, date.2, time.0, time.1, 0).unwrap()
+    }
+
+    #[test]
+    fn fires_today_if_the_time_is_still_ahead() {
+        let warsaw = FixedOffset::east_opt(3600).unwrap();
+        let now = at(&warsaw, (2024, 3, 1), (7, 30));
+        let time = parse_briefing_time("08:00").unwrap();
+        assert_eq!(next_briefing(&now, time), at(&warsaw, (2024, 3, 1), (8, 0)));
+    }
+
+    #[test]
+    fn fires_tomorrow_once_the_time_has_passed() {
+        let warsaw = FixedOffset::east_opt(3600).unwrap();
+        let time = parse_briefing_time("08:00").unwrap();
+        // Exactly at the briefing time counts as passed, so it doesn't fire twice
+        let now = at(&warsaw, (2024, 3, 1), (8, 0));
+        assert_eq!(next_briefing(&now, time), at(&warsaw, (2024, 3, 2), (8, 0)));
+        let now = at(&warsaw, (2024, 12, 31), (23, 59));
+        assert_eq!(next_briefing(&now, time), at(&warsaw, (2025, 1, 1), (8, 0)));
+    }
+
+    #[test]
+    fn parses_only_hh_mm() {
+        assert_eq!(parse_briefing_time("08:00"), Ok(NaiveTime::from_hms_opt(8, 0, 0).unwrap()));
+        assert_eq!(parse_briefing_time(" 23:59 "), Ok(NaiveTime::from_hms_opt(23, 59, 0).unwrap()));
+        assert_eq!(parse_briefing_time("00:00"), Ok(NaiveTime::MIN));
+        for value in ["", "8:00", "08:0", "0800", "08:00:00", "8am", "25:00", "23:60", "24:01", "-1:00", "ab:cd"] {
+            let error = parse_briefing_time(value).unwrap_err();
+            assert!(error.contains("HH:MM"), "{}: {}", value, error);
+        }
+    }
+
+    #[test]
+    fn midnight_can_be_written_as_24_00() {
+        let warsaw = FixedOffset::east_opt(3600).unwrap();
+        let time = parse_briefing_time("24:00").unwrap();
+        assert_eq!(time, NaiveTime::MIN);
+        let now = at(&warsaw, (2024, 3, 1), (22, 0));
+        assert_eq!(next_briefing(&now, time), at(&warsaw, (2024, 3, 2), (0, 0)));
+    }
+
+    #[test]
+    fn due_handles_clock_changes() {
+        let warsaw = FixedOffset::east_opt(3600).unwrap();
+        let fire_at = at(&warsaw, (2024, 3, 2), (8, 0));
+
+        let now = at(&warsaw, (2024, 3, 2), (7, 0));
+        assert_eq!(briefing_due(&now, &fire_at), BriefingDue::Wait(Duration::hours(1)));
+        assert_eq!(briefing_due(&fire_at, &fire_at), BriefingDue::Fire);
+        // A little late (busy machine) still fires
+        let now = at(&warsaw, (2024, 3, 2), (8, 30));
+        assert_eq!(briefing_due(&now, &fire_at), BriefingDue::Fire);
+        // Woke up from sleep in the afternoon, or the clock jumped forward
+        let now = at(&warsaw, (2024, 3, 2), (14, 0));
+        assert_eq!(briefing_due(&now, &fire_at), BriefingDue::Missed);
+        // Clock set back by two days
+        let now = at(&warsaw, (2024, 2, 29), (9, 0));
+        assert_eq!(briefing_due(&now, &fire_at), BriefingDue::Reschedule);
+    }
+
+    #[test]
+    fn geolocation_response_becomes_city_and_country() {
+        let response = serde_json::json!({"status": "success", "city": "Kraków", "countryCode": "PL"});
+        assert_eq!(location_from_geolocation(&response), Ok("Kraków,PL".to_string()));
+        let response = serde_json::json!({"status": "success", "city": "Kraków"});
+        assert_eq!(location_from_geolocation(&response), Ok("Kraków".to_string()));
+
+        let response = serde_json::json!({"status": "fail", "message": "private range"});
+        assert!(location_from_geolocation(&response).unwrap_err().contains("private range"));
+        let response = serde_json::json!({"status": "success", "city": ""});
+        assert!(location_from_geolocation(&response).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+// Do-not-disturb schedule: whether the local time falls in one of the configured windows
+
+use std::cmp::Ordering;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::briefing::parse_briefing_time;
+use crate::settings::DndWindow;
+
+/// Parses a day name such as "mon" or "monday" (any case).
+pub fn parse_weekday(value: &str) -> Result<Weekday, String> {
+    value
+        .trim()
+        .parse::<Weekday>()
+        .map_err(|_| format!("\"{}\" is not a day of the week, expected e.g. \"mon\"", value))
+}
+
+/// Checks a window's times and days, for `Settings::validate`.
+pub fn check_window(window: &DndWindow) -> Result<(), String> {
+    let start = parse_briefing_time(&window.start).map_err(|e| format!("start: {}", e))?;
+    let end = parse_briefing_time(&window.end).map_err(|e| format!("end: {}", e))?;
+    if start == end {
+        return Err(format!("start and end are both {}", window.start));
+    }
+    for day in &window.days {
+        parse_weekday(day)?;
+    }
+    Ok(())
+}
+
+/// Whether `now` (local time) is inside any of `windows`.
+pub fn is_active(windows: &[DndWindow], now: NaiveDateTime) -> bool {
+    windows.iter().any(|window| contains(window, now))
+}
+
+// A window with `end` before `start` runs past midnight; `days` are the days it starts on.
+// Invalid windows (only possible in a hand-edited file) never match.
+fn contains(window: &DndWindow, now: NaiveDateTime) -> bool {
+    let (Ok(start), Ok(end)) = (parse_briefing_time(&window.start), parse_briefing_time(&window.end)) else {
+        return false;
+    };
+    let starts_on = |date: NaiveDate| {
+        window.days.is_empty() || window.days.iter().any(|day| parse_weekday(day) == Ok(date.weekday()))
+    };
+    let (today, time) = (now.date(), now.time());
+    match start.cmp(&end) {
+        Ordering::Less => starts_on(today) && start <= time && time < end,
+        Ordering::Greater => (starts_on(today) && time >= start) || (time < end && today.pred_opt().is_some_and(starts_on)),
+        Ordering::Equal => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str, days: &[&str]) -> DndWindow {
+        DndWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            days: days.iter().map(|day| day.to_string()).collect(),
+        }
+    }
+
+    // 2024-03-04 is a Monday
+    fn at(day: u32, hours: u32, minutes: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap().and_hms_opt(hours, minutes, 0).unwrap()
+    }
+
+    #[test]
+    fn window_covers_start_but_not_end() {
+        let meetings = [window("09:00", "10:30", &[])];
+        assert!(!is_active(&meetings, at(4, 8, 59)));
+        assert!(is_active(&meetings, at(4, 9, 0)));
+        assert!(is_active(&meetings, at(4, 10, 29)));
+        assert!(!is_active(&meetings, at(4, 10, 30)));
+        assert!(!is_active(&[], at(4, 9, 30)));
+    }
+
+    #[test]
+    fn days_limit_the_window() {
+        let workdays = [window("09:00", "17:00", &["mon", "Tuesday", "WED", "thu", "fri"])];
+        assert!(is_active(&workdays, at(4, 12, 0)));
+        assert!(is_active(&workdays, at(8, 12, 0)));
+        // Saturday
+        assert!(!is_active(&workdays, at(9, 12, 0)));
+    }
+
+    #[test]
+    fn night_window_belongs_to_the_day_it_starts() {
+        let sunday_night = [window("22:00", "07:00", &["sun"])];
+        // Sunday 2024-03-03 23:00 and Monday 06:59
+        assert!(is_active(&sunday_night, at(3, 23, 0)));
+        assert!(is_active(&sunday_night, at(4, 6, 59)));
+        assert!(!is_active(&sunday_night, at(4, 7, 0)));
+        // Monday night isn't included
+        assert!(!is_active(&sunday_night, at(4, 23, 0)));
+
+        // "24:00" ends at midnight
+        let evening = [window("20:00", "24:00", &[])];
+        assert!(is_active(&evening, at(4, 23, 59)));
+        assert!(!is_active(&evening, at(5, 0, 0)));
+    }
+
+    #[test]
+    fn invalid_windows_are_reported() {
+        assert!(check_window(&window("22:00", "07:00", &["sun"])).is_ok());
+        assert!(check_window(&window("9:00", "10:00", &[])).unwrap_err().contains("start"));
+        assert!(check_window(&window("09:00", "10:00", &["weekend"])).unwrap_err().contains("weekend"));
+        assert!(check_window(&window("09:00", "09:00", &[])).is_err());
+        // The same moment written two ways
+        assert!(check_window(&window("00:00", "24:00", &[])).is_err());
+    }
+}
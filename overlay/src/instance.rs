@@ -0,0 +1,143 @@
+// Single-instance enforcement. The running overlay listens on a loopback port written to a
+// lock file in the settings directory; a second launch connects to it, asks it to come
+// forward and exits. A lock file left behind by a crash points at a port where nothing
+// answers the handshake, so it is replaced.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+const LOCK_FILE_NAME: &str = "overlay.lock";
+const ACTIVATE_REQUEST: &str = "gaja-overlay activate";
+const ACTIVATE_REPLY: &str = "gaja-overlay ok";
+
+// The running instance answers straight away; a port that is slower than this isn't it
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+type ActivateHandler = Box<dyn Fn() + Send + Sync>;
+
+/// Held by the instance that owns the lock.
+pub struct InstanceLock {
+    on_activate: Arc<OnceLock<ActivateHandler>>,
+}
+
+impl InstanceLock {
+    /// Called whenever another launch asks this instance to come forward.
+    pub fn on_activate(&self, handler: impl Fn() + Send + Sync + 'static) {
+        if self.on_activate.set(Box::new(handler)).is_err() {
+            eprintln!("[Rust] Instance activation handler already set");
+        }
+    }
+}
+
+pub enum Instance {
+    /// No other overlay is running
+    Primary(InstanceLock),
+    /// Another overlay is running and was asked to activate; this one should exit
+    AlreadyRunning,
+}
+
+/// Becomes the running instance, or hands over to the one that already is.
+pub fn acquire(dir: &Path) -> std::io::Result<Instance> {
+    let path = dir.join(LOCK_FILE_NAME);
+    if let Some(port) = fs::read_to_string(&path).ok().and_then(|contents| contents.trim().parse::<u16>().ok()) {
+        if activate_running(port) {
+            return Ok(Instance::AlreadyRunning);
+        }
+        println!("[Rust] Replacing stale instance lock {}", path.display());
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    let on_activate: Arc<OnceLock<ActivateHandler>> = Arc::new(OnceLock::new());
+    // Accepting starts right away so a second launch during startup still gets an answer
+    let handler = on_activate.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if answer_handshake(stream) {
+                match handler.get() {
+                    Some(activate) => activate(),
+                    None => println!("[Rust] Activation requested before startup finished, ignoring"),
+                }
+            }
+        }
+    });
+    fs::write(&path, format!("{}\n", port))?;
+    Ok(Instance::Primary(InstanceLock { on_activate }))
+}
+
+// True if an overlay listening on `port` confirmed the request
+fn activate_running(port: u16) -> bool {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&address, HANDSHAKE_TIMEOUT) else {
+        return false;
+    };
+    if stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).is_err() || writeln!(stream, "{}", ACTIVATE_REQUEST).is_err() {
+        return false;
+    }
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).is_ok() && reply.trim_end() == ACTIVATE_REPLY
+}
+
+fn answer_handshake(mut stream: TcpStream) -> bool {
+    if stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).is_err() {
+        return false;
+    }
+    let mut request = String::new();
+    let Ok(reader) = stream.try_clone() else {
+        return false;
+    };
+    if BufReader::new(reader).read_line(&mut request).is_err() || request.trim_end() != ACTIVATE_REQUEST {
+        return false;
+    }
+    writeln!(stream, "{}", ACTIVATE_REPLY).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("gaja_overlay_instance_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn second_launch_activates_the_first() {
+        let dir = temp_dir("second_launch");
+        let Instance::Primary(lock) = acquire(&dir).unwrap() else {
+            panic!("first launch should own the lock");
+        };
+        let activations = Arc::new(AtomicU32::new(0));
+        let counter = activations.clone();
+        lock.on_activate(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert!(matches!(acquire(&dir).unwrap(), Instance::AlreadyRunning));
+        // The handler runs after the reply, give the listener thread a moment
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(activations.load(Ordering::SeqCst), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stale_lock_is_replaced() {
+        let dir = temp_dir("stale_lock");
+        // A port that was free a moment ago, like one left behind by a crashed overlay
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+        fs::write(dir.join(LOCK_FILE_NAME), format!("{}\n", port)).unwrap();
+        assert!(matches!(acquire(&dir).unwrap(), Instance::Primary(_)));
+
+        // Something other than an overlay listening on the recorded port doesn't count either
+        let other = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        fs::write(dir.join(LOCK_FILE_NAME), format!("{}\n", other.local_addr().unwrap().port())).unwrap();
+        assert!(matches!(acquire(&dir).unwrap(), Instance::Primary(_)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+// Tails the server log over server-sent events and forwards each line to the frontend
+
+use crate::{describe_request_error, settings, sse, HTTP_REQUEST_TIMEOUT};
+use futures_util::StreamExt;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// The running log stream task, if any.
+pub type LogStreamState = Mutex<Option<tauri::async_runtime::JoinHandle<()>>>;
+
+/// Starts streaming `log_stream_url`, replacing a stream that is already running.
+/// Every line arrives as a "server-log" event; "server-log-stopped" says why the stream ended.
+#[tauri::command]
+pub async fn start_log_stream(
+    app_handle: AppHandle,
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    log_stream: tauri::State<'_, LogStreamState>,
+) -> Result<(), String> {
+    let url = settings.lock().unwrap().log_stream_url.clone();
+    if url.is_empty() {
+        return Err("Set log_stream_url to the server's log stream endpoint first".to_string());
+    }
+    let response = tokio::time::timeout(HTTP_REQUEST_TIMEOUT, sse::request(&client, &url, None).send())
+        .await
+        .map_err(|_| format!("Log stream at {} did not answer", url))?
+        .map_err(|e| format!("Failed to connect to the log stream: {}", describe_request_error(&e)))?;
+    if !response.status().is_success() {
+        return Err(format!("Log stream at {} returned status: {}", url, response.status()));
+    }
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    if !sse::is_event_stream(content_type) {
+        return Err(format!("{} is not an event stream (Content-Type {:?})", url, content_type));
+    }
+
+    println!("[Rust] Streaming server log from {}", url);
+    let emit_handle = app_handle.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let reason = forward_log_lines(response, &emit_handle).await;
+        println!("[Rust] Server log stream stopped: {}", reason);
+        emit_handle
+            .emit_all("server-log-stopped", reason)
+            .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit server-log-stopped: {}", e));
+    });
+    if let Some(previous) = log_stream.lock().unwrap().replace(task) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_log_stream(log_stream: tauri::State<'_, LogStreamState>) -> Result<(), String> {
+    stop(&log_stream);
+    Ok(())
+}
+
+/// Aborts the running stream, returns false if there was none.
+pub fn stop(log_stream: &LogStreamState) -> bool {
+    match log_stream.lock().unwrap().take() {
+        Some(task) => {
+            println!("[Rust] Stopping server log stream");
+            task.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+// Runs until the stream ends, returns why it did. No idle timeout: a quiet server logs nothing.
+async fn forward_log_lines(response: reqwest::Response, app_handle: &AppHandle) -> String {
+    let mut stream = response.bytes_stream();
+    let mut parser = sse::SseParser::default();
+    while let Some(chunk) = stream.next().await {
+        let bytes = match chunk {
+            Ok(bytes) => bytes,
+            Err(e) => return format!("Log stream error: {}", e),
+        };
+        for event in parser.push(&bytes) {
+            for line in event.data.lines() {
+                app_handle
+                    .emit_all("server-log", line)
+                    .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit server-log: {}", e));
+            }
+        }
+    }
+    "The server closed the log stream".to_string()
+}
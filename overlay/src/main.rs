@@ -1,387 +1,3593 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::{Manager, AppHandle, Window, WindowEvent}; // Removed SystemTray, SystemTrayEvent
+mod actions;
+mod audio;
+mod briefing;
+mod bundle;
+mod dnd;
+mod instance;
+mod log_stream;
+mod metrics;
+mod pin;
+mod placement;
+mod poll_rate;
+mod priority;
+mod raw_log;
+mod session;
+mod settings;
+mod snapshot;
+mod sse;
+mod status;
+mod throttle;
+mod tuning;
+mod ui_fallback;
+
+use tauri::{Manager, AppHandle, Window, WindowEvent, CustomMenuItem, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem};
 use tokio::time::sleep;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle}; // Added HasRawWindowHandle
 use std::time::{Instant, Duration};
 use futures_util::StreamExt;
+use status::Status;
 
-#[derive(Clone, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 struct StatusUpdate {
     status: String,
+    state: Status,
+    display_status: String,
+    // Cut to MAX_STATUS_TEXT_CHARS
     text: String,
+    // The whole text when `text` was cut, for the frontend to expand on demand
+    full_text: Option<String>,
+    // True while `text` is an incremental chunk of a response that is still streaming
+    partial: bool,
     is_listening: bool,
     is_speaking: bool,
     wake_word_detected: bool,
+    connection: ConnectionStatus,
 }
 
-#[derive(Debug, Deserialize, Clone)] // Added Clone
-struct AssistantStatusResponse {
-    status: String,
-    text: Option<String>,
-    message: Option<String>, // For compatibility with potential variations
-    // Add other fields if your Python server sends more, e.g.,
-    // is_listening: Option<bool>,
-    // is_speaking: Option<bool>,
-    // wake_word_detected: Option<bool>,
+// Whether the content above is current, so the frontend can dim it while reconnecting
+#[derive(Debug, Clone, Serialize)]
+struct ConnectionStatus {
+    connected: bool,
+    // "sse" or "polling", None while not connected
+    transport: Option<&'static str>,
+    // Connection cycles failed in a row, 0 while the client answers
+    reconnect_attempt: u32,
+}
+
+// One received status payload and what the overlay made of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StatusHistoryEntry {
+    // Milliseconds since the Unix epoch
+    received_at_ms: u64,
+    state: Status,
+    visible: bool,
+    payload: serde_json::Value,
 }
 
+const STATUS_HISTORY_LEN: usize = 100;
+
 #[derive(Debug, Clone, Serialize)] // Added Clone and Serialize
 pub struct OverlayState {
     visible: bool,
     status: String,
+    state: Status,
     text: String,
+    partial: bool,
     is_listening: bool,
     is_speaking: bool,
     wake_word_detected: bool,
     #[serde(skip_serializing)]
     last_activity_time: Instant,
+    #[serde(skip_serializing)]
+    current_monitor: Option<String>,
+    // Last time follow_active_monitor moved the overlay
+    #[serde(skip_serializing)]
+    last_monitor_follow: Option<Instant>,
+    #[serde(skip_serializing)]
+    last_good_port: Option<u16>,
+    // Address that answered on last_good_port (localhost may be reachable on only one of IPv4/IPv6)
+    #[serde(skip_serializing)]
+    last_good_base_url: Option<String>,
+    connected: bool,
+    // Click-through temporarily disabled so the user can click overlay content
+    interactive: bool,
+    #[serde(skip_serializing)]
+    interactive_generation: u64,
+    // Notification payloads waiting for the one on screen to reach its minimum duration
+    #[serde(skip_serializing)]
+    notification_queue: VecDeque<serde_json::Value>,
+    #[serde(skip_serializing)]
+    notification_shown_at: Option<Instant>,
+    // Until then, updates that would take the wake word acknowledgement off screen are held
+    #[serde(skip_serializing)]
+    wake_ack_until: Option<Instant>,
+    // Latest update held back by wake_ack_until, applied once it passes
+    #[serde(skip_serializing)]
+    held_after_ack: Option<serde_json::Value>,
+    // Last payload received before clear_overlay; polling repeats it, so it is ignored until it changes
+    #[serde(skip_serializing)]
+    dismissed_payload: Option<serde_json::Value>,
+    // Last payload received, whatever became of it
+    #[serde(skip_serializing)]
+    last_payload: Option<serde_json::Value>,
+    // Last STATUS_HISTORY_LEN payloads, oldest first
+    #[serde(skip_serializing)]
+    status_history: VecDeque<StatusHistoryEntry>,
+    // Voice settings were saved locally but the client hasn't received them yet
+    voice_sync_pending: bool,
+    // Same for the audio device selection
+    audio_sync_pending: bool,
+    // Overlay hidden and no requests to the client until unpaused
+    paused: bool,
+    // Until then only live states may show the overlay, and only if overlay.live_during_snooze is set
+    #[serde(skip_serializing)]
+    snoozed_until: Option<Instant>,
+    // Client payloads are being recorded by record_session
+    #[serde(skip_serializing)]
+    session_recording: Option<session::Recorder>,
+    // While replay_session runs, payloads from the client are ignored
+    #[serde(skip_serializing)]
+    replaying_session: bool,
+    // Hidden by the user; status updates may change content but never show the window
+    user_disabled: bool,
+    // Why the last attempt to reach the client failed, cleared once connected
+    last_error: Option<String>,
+    // Set when the last scan found something other than the client answering, cleared once connected
+    unexpected_response: Option<status::UnexpectedResponse>,
+    // Why the overlay couldn't be placed on the primary monitor at the last placement
+    monitor_warning: Option<String>,
+    // Inside one of the Settings.do_not_disturb windows, as of the last check
+    dnd_active: bool,
+    // What the client advertised at /api/info on the current connection, if anything
+    server_info: Option<ServerInfo>,
+    // Set by set_transport and set_active_port, drops the current connection cycle
+    #[serde(skip_serializing)]
+    connection_changed: bool,
+    // Port chosen with set_active_port; discovery only tries this one while set
+    #[serde(skip_serializing)]
+    forced_port: Option<u16>,
+    // Set by reconnect_now, consumed by the connection loop
+    #[serde(skip_serializing)]
+    reconnect_requested: bool,
+    // Until then the client is treated as unreachable (inject_connection_fault)
+    #[serde(skip_serializing)]
+    connection_fault_until: Option<Instant>,
+    // Last payload that carried an action; polling repeats it, so it only runs once
+    #[serde(skip_serializing)]
+    last_action: Option<serde_json::Value>,
+    // Whether the client is in wake word tuning mode, and the trial sensitivity
+    #[serde(skip_serializing)]
+    tuning: tuning::Tuning,
+    // When the scheduler will next request the daily briefing, None while it's off
+    #[serde(skip_serializing)]
+    next_briefing_at: Option<chrono::DateTime<chrono::Local>>,
+    // Result of the IP location lookup, used while daily_briefing.location is empty
+    #[serde(skip_serializing)]
+    detected_location: Option<String>,
+    // Wake words the client listed at /api/wake_words, cached for the rest of the run
+    #[serde(skip_serializing)]
+    available_wake_words: Option<Vec<String>>,
+    // How often the watchdog had to relaunch the connection loop
+    #[serde(skip_serializing)]
+    poll_task_restarts: u32,
+    // Status payloads received since startup, for diagnostics and metrics
+    #[serde(skip_serializing)]
+    status_updates: u64,
+    // Connection cycles that ended and were retried
+    #[serde(skip_serializing)]
+    reconnects: u64,
+    // "sse" or "polling" while a transport is running
+    #[serde(skip_serializing)]
+    transport: Option<&'static str>,
+    // Connection cycles failed in a row since the client last sent data
+    #[serde(skip_serializing)]
+    reconnect_attempt: u32,
+    // Last SSE event id, sent as Last-Event-ID when the stream reconnects
+    #[serde(skip_serializing)]
+    sse_last_event_id: Option<String>,
+    // How long status requests take in polling mode
+    #[serde(skip_serializing)]
+    poll_latency: metrics::Histogram,
+    #[serde(skip_serializing)]
+    status_emit_throttle: throttle::EmitThrottle<StatusUpdate>,
+}
+
+impl OverlayState {
+    fn connection_fault_active(&self) -> bool {
+        self.connection_fault_until.is_some_and(|until| until > Instant::now())
+    }
+
+    fn snooze_active(&self) -> bool {
+        self.snoozed_until.is_some_and(|until| until > Instant::now())
+    }
+
+    fn new() -> Self {
+        OverlayState {
+            visible: false,
+            status: "Offline".to_string(),
+            state: Status::Idle,
+            text: "".to_string(),
+            partial: false,
+            is_listening: false,
+            is_speaking: false,
+            wake_word_detected: false,
+            last_activity_time: Instant::now(),
+            current_monitor: None,
+            last_monitor_follow: None,
+            last_good_port: None,
+            last_good_base_url: None,
+            connected: false,
+            interactive: false,
+            interactive_generation: 0,
+            notification_queue: VecDeque::new(),
+            notification_shown_at: None,
+            wake_ack_until: None,
+            held_after_ack: None,
+            dismissed_payload: None,
+            last_payload: None,
+            status_history: VecDeque::with_capacity(STATUS_HISTORY_LEN),
+            voice_sync_pending: false,
+            audio_sync_pending: false,
+            paused: false,
+            snoozed_until: None,
+            session_recording: None,
+            replaying_session: false,
+            user_disabled: false,
+            last_error: None,
+            unexpected_response: None,
+            monitor_warning: None,
+            dnd_active: false,
+            server_info: None,
+            connection_changed: false,
+            forced_port: None,
+            reconnect_requested: false,
+            connection_fault_until: None,
+            last_action: None,
+            tuning: tuning::Tuning::default(),
+            next_briefing_at: None,
+            detected_location: None,
+            available_wake_words: None,
+            poll_task_restarts: 0,
+            status_updates: 0,
+            reconnects: 0,
+            transport: None,
+            reconnect_attempt: 0,
+            sse_last_event_id: None,
+            poll_latency: metrics::Histogram::default(),
+            status_emit_throttle: throttle::EmitThrottle::new(STATUS_EMIT_MIN_INTERVAL),
+        }
+    }
+}
+
+type SharedState = Arc<Mutex<OverlayState>>;
+
+// Label of the window that shows the overlay. Showing, hiding, placement and overlay events
+// all go through overlay_window() instead of assuming "main". Kept apart from OverlayState
+// so it can be looked up while that is locked.
+struct OverlayWindow(Mutex<String>);
+
+const DEFAULT_OVERLAY_WINDOW: &str = "main";
+
+impl Default for OverlayWindow {
+    fn default() -> Self {
+        OverlayWindow(Mutex::new(DEFAULT_OVERLAY_WINDOW.to_string()))
+    }
+}
+
+fn overlay_window_label(app_handle: &AppHandle) -> String {
+    app_handle.state::<OverlayWindow>().0.lock().unwrap().clone()
+}
+
+fn overlay_window(app_handle: &AppHandle) -> Result<Window, String> {
+    let label = overlay_window_label(app_handle);
+    app_handle.get_window(&label).ok_or_else(|| format!("Overlay window '{}' not found", label))
+}
+
+fn is_overlay_window(window: &Window) -> bool {
+    window.label() == overlay_window_label(&window.app_handle())
+}
+
+// Size of the overlay content last reported by the frontend (set_content_size), in CSS
+// pixels. Like OverlayWindow, kept apart from OverlayState because placement reads it while
+// that is locked.
+#[derive(Default)]
+struct ContentSize(Mutex<Option<tauri::LogicalSize<f64>>>);
+
+// Application window the overlay is pinned to (pin_to_window); while set, placement leaves
+// the overlay to the tracker. `generation` changes on every pin and unpin, so a tracker
+// for an earlier pin stops.
+#[derive(Default)]
+struct PinnedWindow {
+    target: Mutex<Option<pin::PinTarget>>,
+    generation: std::sync::atomic::AtomicU64,
+}
+
+// Non-critical status-update events are coalesced to at most 20 per second
+const STATUS_EMIT_MIN_INTERVAL: Duration = Duration::from_millis(50);
+
+// Longer response text is cut in status-update events, so a paragraph doesn't fill the screen
+const MAX_STATUS_TEXT_CHARS: usize = 600;
+// Status text in the log is cut further; raw logging still shows all of it
+const MAX_LOGGED_TEXT_CHARS: usize = 80;
+
+fn text_for_log(text: &str) -> String {
+    match status::truncate_text(text, MAX_LOGGED_TEXT_CHARS) {
+        Some(short) => format!("{} ({} chars)", short, text.chars().count()),
+        None => text.to_string(),
+    }
+}
+
+// What the frontend should show for the current state, with labels in `language`
+fn current_status_update(state_guard: &OverlayState, language: &str) -> StatusUpdate {
+    let truncated = status::truncate_text(&state_guard.text, MAX_STATUS_TEXT_CHARS);
+    StatusUpdate {
+        status: state_guard.status.clone(),
+        state: state_guard.state,
+        display_status: status::display_status(state_guard.state, language, &state_guard.status),
+        full_text: truncated.is_some().then(|| state_guard.text.clone()),
+        text: truncated.unwrap_or_else(|| state_guard.text.clone()),
+        partial: state_guard.partial,
+        is_listening: state_guard.is_listening,
+        is_speaking: state_guard.is_speaking,
+        wake_word_detected: state_guard.wake_word_detected,
+        connection: ConnectionStatus {
+            connected: state_guard.connected,
+            transport: state_guard.transport,
+            reconnect_attempt: state_guard.reconnect_attempt,
+        },
+    }
+}
+
+// Emits a status-update now for live states, otherwise through the throttle
+fn emit_status_update(window: &Window, state: &SharedState, state_guard: &mut OverlayState, payload: StatusUpdate) {
+    let critical = payload.state.is_live();
+    match state_guard.status_emit_throttle.offer(payload, Instant::now(), critical) {
+        throttle::Offer::EmitNow(payload) => {
+            raw_log::emitted("status-update", &payload);
+            window.emit("status-update", payload).unwrap_or_else(|e| {
+                eprintln!("Failed to emit status-update: {}", e);
+            });
+        }
+        throttle::Offer::Schedule(delay) => {
+            let window = window.clone();
+            let state = state.clone();
+            tauri::async_runtime::spawn(async move {
+                sleep(delay).await;
+                let pending = state.lock().unwrap().status_emit_throttle.take_pending(Instant::now());
+                if let Some(payload) = pending {
+                    raw_log::emitted("status-update", &payload);
+                    window.emit("status-update", payload).unwrap_or_else(|e| {
+                        eprintln!("Failed to emit status-update: {}", e);
+                    });
+                }
+            });
+        }
+        throttle::Offer::Coalesced => {}
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct OverlayVisibilityEvent {
+    // "content", "command", "startup", "auto-hide", "paused", "snoozed", "debug", "restored" or "load-failed"
+    reason: &'static str,
+}
+
+// Shows or hides the overlay window. On a change, also emits "overlay-shown" or "overlay-hidden"
+// so the frontend can reset or prepare its content.
+fn set_overlay_visible(window: &Window, overlay_state: &mut OverlayState, visible: bool, reason: &'static str) -> Result<(), String> {
+    if visible {
+        window.show().map_err(|e| e.to_string())?;
+    } else {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+    if overlay_state.visible != visible {
+        overlay_state.visible = visible;
+        let event = if visible { "overlay-shown" } else { "overlay-hidden" };
+        println!("[Rust] {} ({})", event, reason);
+        raw_log::emitted(event, &OverlayVisibilityEvent { reason });
+        window.emit(event, OverlayVisibilityEvent { reason }).unwrap_or_else(|e| {
+            eprintln!("Failed to emit {}: {}", event, e);
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn show_overlay(app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    let window = overlay_window(&app_handle)?;
+    set_overlay_visible(&window, &mut state.lock().unwrap(), true, "command")
+}
+
+#[tauri::command]
+async fn hide_overlay(app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    let window = overlay_window(&app_handle)?;
+    set_overlay_visible(&window, &mut state.lock().unwrap(), false, "command")
+}
+
+// Whether `window` loads the overlay page (index.html) rather than settings.html or another page
+fn shows_overlay_page(window: &Window) -> bool {
+    let url = window.url();
+    url.path() == "/" || url.path().ends_with("/index.html")
+}
+
+// Makes another window the overlay, e.g. one created for a second monitor. The previous
+// overlay window is hidden; the new one gets the placement, visibility and current status.
+#[tauri::command]
+async fn set_overlay_window(label: String, app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    let window = app_handle.get_window(&label).ok_or_else(|| format!("Window '{}' not found", label))?;
+    // It would be made click-through and topmost, with the overlay hidden behind it
+    if label == "settings" || !shows_overlay_page(&window) {
+        return Err(format!("Window '{}' is not an overlay window", label));
+    }
+    let previous = overlay_window(&app_handle).ok();
+    *app_handle.state::<OverlayWindow>().0.lock().unwrap() = label.clone();
+    if let Some(previous) = previous.filter(|previous| previous.label() != label) {
+        previous.hide().unwrap_or_else(|e| eprintln!("[Rust] Failed to hide the previous overlay window: {}", e));
+    }
+    println!("[Rust] Overlay window is now '{}'", label);
+
+    refresh_overlay_placement(&window);
+    let language = app_handle.state::<settings::SharedSettings>().lock().unwrap().voice.language.clone();
+    let mut state_guard = state.lock().unwrap();
+    if state_guard.visible {
+        window.show().map_err(|e| e.to_string())?;
+    }
+    let payload = current_status_update(&state_guard, &language);
+    emit_status_update(&window, &state, &mut state_guard, payload);
+    Ok(())
+}
+
+// How often a pinned window's position is checked
+const PIN_TRACK_INTERVAL: Duration = Duration::from_millis(200);
+
+// Pins the overlay over the first other window whose title contains `title_substring`, and
+// keeps it there until unpin_window or the window closes. Returns the window's full title.
+#[tauri::command]
+async fn pin_to_window(title_substring: String, app_handle: AppHandle) -> Result<String, String> {
+    let target = pin::find_window(&title_substring)?;
+    let pinned = app_handle.state::<PinnedWindow>();
+    let generation = pinned.generation.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+    *pinned.target.lock().unwrap() = Some(target.clone());
+    println!("[Rust] Overlay pinned to window \"{}\"", target.title);
+    tauri::async_runtime::spawn(track_pinned_window(app_handle.clone(), target.clone(), generation));
+    Ok(target.title)
+}
+
+#[tauri::command]
+async fn unpin_window(app_handle: AppHandle) -> Result<(), String> {
+    unpin(&app_handle, "command");
+    Ok(())
+}
+
+// Puts the overlay back where its settings place it and emits "overlay-unpinned" with `reason`
+fn unpin(app_handle: &AppHandle, reason: &'static str) {
+    let pinned = app_handle.state::<PinnedWindow>();
+    pinned.generation.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    let Some(target) = pinned.target.lock().unwrap().take() else {
+        return;
+    };
+    println!("[Rust] Overlay unpinned from window \"{}\" ({})", target.title, reason);
+    match overlay_window(app_handle) {
+        Ok(window) => refresh_overlay_placement(&window),
+        Err(e) => eprintln!("[Rust] {}, not placing it", e),
+    }
+    app_handle
+        .emit_all("overlay-unpinned", reason)
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit overlay-unpinned: {}", e));
+}
+
+// Keeps the overlay over `target` until it is unpinned or pinned again
+async fn track_pinned_window(app_handle: AppHandle, target: pin::PinTarget, generation: u64) {
+    let state = app_handle.state::<SharedState>().inner().clone();
+    let mut previous = None;
+    // Shown again when the target is restored only if the overlay was up when it was minimized
+    let mut hidden_while_minimized = false;
+    loop {
+        if app_handle.state::<PinnedWindow>().generation.load(std::sync::atomic::Ordering::SeqCst) != generation {
+            return;
+        }
+        let current = pin::target_state(&target);
+        let window = match overlay_window(&app_handle) {
+            Ok(window) => window,
+            Err(e) => {
+                eprintln!("[Rust] {}, pinned window not followed", e);
+                sleep(PIN_TRACK_INTERVAL).await;
+                continue;
+            }
+        };
+        match pin::next_action(previous, current) {
+            pin::PinAction::None => {}
+            pin::PinAction::Unpin => {
+                unpin(&app_handle, "closed");
+                return;
+            }
+            pin::PinAction::Hide => {
+                let mut state_guard = state.lock().unwrap();
+                if state_guard.visible {
+                    hidden_while_minimized = true;
+                    set_overlay_visible(&window, &mut state_guard, false, "pinned-window-minimized")
+                        .unwrap_or_else(|e| eprintln!("Failed to hide window: {}", e));
+                }
+            }
+            pin::PinAction::Place { position, size, restored } => {
+                window.set_position(position).unwrap_or_else(|e| eprintln!("Failed to set window position: {}", e));
+                window.set_size(size).unwrap_or_else(|e| eprintln!("Failed to set window size: {}", e));
+                if restored && std::mem::take(&mut hidden_while_minimized) {
+                    set_overlay_visible(&window, &mut state.lock().unwrap(), true, "pinned-window-restored")
+                        .unwrap_or_else(|e| eprintln!("Failed to show window: {}", e));
+                }
+            }
+        }
+        previous = Some(current);
+        sleep(PIN_TRACK_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+fn get_overlay_window(app_handle: AppHandle) -> Result<String, String> {
+    Ok(overlay_window_label(&app_handle))
+}
+
+#[tauri::command]
+async fn toggle_overlay_display(app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<bool, String> {
+    toggle_overlay(&app_handle, &state)
+}
+
+// Flips overlay visibility, returns the new visibility. Hiding it this way also keeps
+// status updates from showing it again until the user toggles it back on.
+fn toggle_overlay(app_handle: &AppHandle, state: &SharedState) -> Result<bool, String> {
+    let window = overlay_window(app_handle)?;
+    let visible = {
+        let mut overlay_state = state.lock().unwrap();
+        let visible = !overlay_state.visible;
+        set_overlay_visible(&window, &mut overlay_state, visible, "command")?;
+        overlay_state.user_disabled = !visible;
+        visible
+    };
+    println!("[Rust] Overlay display toggled, visible={}", visible);
+
+    let user_disabled = !visible;
+    let settings = app_handle.state::<settings::SharedSettings>();
+    let mut settings_guard = settings.lock().unwrap();
+    if settings_guard.overlay.user_disabled != user_disabled {
+        let mut updated = settings_guard.clone();
+        updated.overlay.user_disabled = user_disabled;
+        app_handle.state::<settings::SettingsWriter>().schedule(updated.clone());
+        *settings_guard = updated;
+    }
+    Ok(visible)
+}
+
+// Changes overlay.z_order and, if given, overlay.reassert_topmost_ms. Setting "top" again
+// also puts the overlay back on top if a game pushed it down.
+#[tauri::command]
+async fn set_z_order(
+    z_order: String,
+    reassert_topmost_ms: Option<u64>,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.overlay.z_order = z_order.clone();
+        if let Some(interval_ms) = reassert_topmost_ms {
+            updated.overlay.reassert_topmost_ms = interval_ms;
+        }
+        updated.validate()?;
+        app_handle.state::<settings::SettingsWriter>().schedule(updated.clone());
+        *settings_guard = updated;
+    }
+    println!("[Rust] Overlay z-order set to {}", z_order);
+
+    let window = overlay_window(&app_handle)?;
+    // Applied now unless the user is interacting, in which case the interactive revert applies it
+    if !state.lock().unwrap().interactive {
+        apply_z_order(&window);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_overlay_click_through(
+    enabled: bool,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.overlay.click_through = enabled;
+        app_handle.state::<settings::SettingsWriter>().schedule(updated.clone());
+        *settings_guard = updated;
+    }
+    println!("[Rust] Overlay click-through {}", if enabled { "enabled" } else { "disabled" });
+
+    let window = overlay_window(&app_handle)?;
+    let interactive = state.lock().unwrap().interactive;
+    set_click_through(&window, !interactive);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_display_mode(
+    mode: String,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>
+) -> Result<(), String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.overlay.display_mode = mode.clone();
+        updated.validate()?;
+        app_handle.state::<settings::SettingsWriter>().schedule(updated.clone());
+        *settings_guard = updated;
+    }
+    println!("[Rust] Overlay display mode set to {}", mode);
+
+    let window = overlay_window(&app_handle)?;
+    refresh_overlay_placement(&window);
+    window.emit("display-mode", &mode).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_text_scale(
+    scale: f64,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>
+) -> Result<(), String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.overlay.text_scale = scale;
+        updated.validate()?;
+        app_handle.state::<settings::SettingsWriter>().schedule(updated.clone());
+        *settings_guard = updated;
+    }
+    println!("[Rust] Overlay text scale set to {}", scale);
+
+    let window = overlay_window(&app_handle)?;
+    window.emit("text-scale", scale).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Saves the theme colors and sends them to every window ("theme-changed"); the windows read
+// them from the settings at startup
+#[tauri::command]
+async fn set_theme(
+    theme: settings::ThemeSettings,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    writer: tauri::State<'_, settings::SettingsWriter>
+) -> Result<(), String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.theme = theme.clone();
+        updated.validate()?;
+        writer.write_now(&updated)?;
+        *settings_guard = updated;
+    }
+    println!("[Rust] Theme set to {:?}", theme);
+    app_handle.emit_all("theme-changed", theme).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Serialize)]
+struct ContentOffset {
+    x: i32,
+    y: i32,
+}
+
+fn window_logical_size(window: &Window) -> Result<tauri::LogicalSize<f64>, String> {
+    let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
+    Ok(window.inner_size().map_err(|e| e.to_string())?.to_logical(scale_factor))
+}
+
+// Offset clamped to the current window size; the frontend translates its content by it
+fn content_offset(window: &Window) -> Result<ContentOffset, String> {
+    let (x, y) = {
+        let settings = window.state::<settings::SharedSettings>();
+        let settings_guard = settings.lock().unwrap();
+        (settings_guard.overlay.content_offset_x, settings_guard.overlay.content_offset_y)
+    };
+    let window_size = window_logical_size(window)?;
+    let (x, y) = placement::clamp_content_offset(x, y, window_size);
+    Ok(ContentOffset { x, y })
+}
+
+fn emit_content_offset(window: &Window) {
+    match content_offset(window) {
+        Ok(offset) => window
+            .emit("content-offset", offset)
+            .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit content-offset: {}", e)),
+        Err(e) => eprintln!("[Rust] Failed to get the content offset: {}", e),
+    }
+}
+
+// Nudges the overlay content without moving or resizing the window. Returns the offset
+// actually used, which is clamped so the content stays on screen. The requested offset is
+// saved, so a window that is briefly smaller (e.g. during a monitor switch) doesn't shrink it.
+#[tauri::command]
+async fn set_content_offset(
+    x: i32,
+    y: i32,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>
+) -> Result<ContentOffset, String> {
+    let window = overlay_window(&app_handle)?;
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.overlay.content_offset_x = x;
+        updated.overlay.content_offset_y = y;
+        app_handle.state::<settings::SettingsWriter>().schedule(updated.clone());
+        *settings_guard = updated;
+    }
+    let offset = content_offset(&window)?;
+    println!("[Rust] Overlay content offset set to ({}, {}), using ({}, {})", x, y, offset.x, offset.y);
+    window.emit("content-offset", offset.clone()).map_err(|e| e.to_string())?;
+    Ok(offset)
+}
+
+// Called by the frontend whenever its content box changes size. With overlay.fit_to_content the
+// window is resized around it; otherwise the size is only remembered.
+#[tauri::command]
+async fn set_content_size(width: f64, height: f64, app_handle: AppHandle) -> Result<(), String> {
+    if !(width.is_finite() && height.is_finite() && width >= 0.0 && height >= 0.0) {
+        return Err(format!("Invalid content size {}x{}", width, height));
+    }
+    let size = tauri::LogicalSize::new(width, height);
+    let previous = app_handle.state::<ContentSize>().0.lock().unwrap().replace(size);
+    let fit_to_content = app_handle.state::<settings::SharedSettings>().lock().unwrap().overlay.fit_to_content;
+    if fit_to_content && previous != Some(size) {
+        let window = overlay_window(&app_handle)?;
+        let monitor = window.current_monitor().map_err(|e| e.to_string())?;
+        match monitor {
+            Some(monitor) => place_overlay_on(&window, &app_handle.state::<settings::SharedSettings>(), &monitor),
+            None => place_overlay(&window, &app_handle.state::<settings::SharedSettings>()),
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_content_offset(app_handle: AppHandle) -> Result<ContentOffset, String> {
+    let window = overlay_window(&app_handle)?;
+    content_offset(&window)
+}
+
+// Saves and applies Settings.process_priority. On other systems the setting is saved but
+// nothing changes, and the returned message says so.
+#[tauri::command]
+fn set_process_priority(
+    priority: String,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    writer: tauri::State<'_, settings::SettingsWriter>
+) -> Result<String, String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.process_priority = priority.clone();
+        updated.validate()?;
+        writer.write_now(&updated)?;
+        *settings_guard = updated;
+    }
+    let level = priority::Priority::from_setting(&priority).ok_or("Invalid process priority")?;
+    Ok(match priority::apply(level) {
+        Ok(()) => {
+            println!("[Rust] Process priority set to {}", priority);
+            format!("Process priority set to {}", priority)
+        }
+        Err(e) => {
+            eprintln!("[Rust] Process priority not changed: {}", e);
+            format!("Saved, but not applied: {}", e)
+        }
+    })
+}
+
+#[tauri::command]
+fn get_process_priority() -> Result<String, String> {
+    priority::current()
+}
+
+// Dismisses whatever the overlay shows, independent of the client. A notification that
+// hasn't been up for notification_min_duration_ms yet is cleared once it has.
+#[tauri::command]
+async fn clear_overlay(app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    request_clear_overlay(&app_handle, &state)
+}
+
+fn request_clear_overlay(app_handle: &AppHandle, state: &SharedState) -> Result<(), String> {
+    let min_duration = Duration::from_millis(
+        app_handle.state::<settings::SharedSettings>().lock().unwrap().overlay.notification_min_duration_ms,
+    );
+    let remaining = {
+        let state_guard = state.lock().unwrap();
+        state_guard
+            .notification_shown_at
+            .filter(|_| state_guard.state == Status::Notification)
+            .and_then(|shown_at| min_duration.checked_sub(shown_at.elapsed()))
+    };
+    match remaining {
+        Some(remaining) => {
+            println!("[Rust] Clearing the overlay in {:?}, after the notification's minimum duration", remaining);
+            let (app_handle, state) = (app_handle.clone(), state.clone());
+            tauri::async_runtime::spawn(async move {
+                sleep(remaining).await;
+                clear_overlay_now(&app_handle, &state).unwrap_or_else(|e| eprintln!("[Rust] Failed to clear overlay: {}", e));
+            });
+            Ok(())
+        }
+        None => clear_overlay_now(app_handle, state),
+    }
+}
+
+fn clear_overlay_now(app_handle: &AppHandle, state: &SharedState) -> Result<(), String> {
+    let window = overlay_window(app_handle)?;
+    let language = app_handle.state::<settings::SharedSettings>().lock().unwrap().voice.language.clone();
+    let mut state_guard = state.lock().unwrap();
+    state_guard.dismissed_payload = state_guard.last_payload.clone();
+    state_guard.notification_queue.clear();
+    state_guard.notification_shown_at = None;
+    state_guard.held_after_ack = None;
+    state_guard.wake_ack_until = None;
+    state_guard.state = Status::Idle;
+    state_guard.status = String::new();
+    state_guard.text = String::new();
+    state_guard.partial = false;
+    state_guard.is_listening = false;
+    state_guard.is_speaking = false;
+    state_guard.wake_word_detected = false;
+    let payload = current_status_update(&state_guard, &language);
+    emit_status_update(&window, state, &mut state_guard, payload);
+    set_overlay_visible(&window, &mut state_guard, false, "cleared")?;
+    println!("[Rust] Overlay cleared");
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_paused(paused: bool, app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    set_paused_state(&app_handle, &state, paused)
+}
+
+fn set_paused_state(app_handle: &AppHandle, state: &SharedState, paused: bool) -> Result<(), String> {
+    let window = overlay_window(app_handle)?;
+    {
+        let mut overlay_state = state.lock().unwrap();
+        overlay_state.paused = paused;
+        if paused {
+            set_overlay_visible(&window, &mut overlay_state, false, "paused")?;
+            overlay_state.status = "Paused".to_string();
+        }
+    }
+    let title = if paused { "Resume overlay" } else { "Pause overlay" };
+    app_handle
+        .tray_handle()
+        .get_item("pause")
+        .set_title(title)
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to update tray menu: {}", e));
+    println!("[Rust] Overlay {}", if paused { "paused" } else { "resumed" });
+    Ok(())
+}
+
+// Longest snooze; for longer, pause or hide the overlay
+const SNOOZE_MAX_MINUTES: u32 = 24 * 60;
+// Snooze length of the tray item
+const TRAY_SNOOZE_MINUTES: u32 = 15;
+
+#[derive(Clone, Serialize)]
+struct SnoozeStartedEvent {
+    minutes: u32,
+    // RFC 3339, local time
+    ends_at: String,
+}
+
+// Hides the overlay for `minutes`, 0 ends a snooze early. Unlike pausing, the connection stays
+// up and the snooze ends by itself.
+#[tauri::command]
+async fn snooze_overlay(minutes: u32, app_handle: AppHandle, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    snooze(&app_handle, &state, minutes)
+}
+
+fn snooze(app_handle: &AppHandle, state: &SharedState, minutes: u32) -> Result<(), String> {
+    if minutes > SNOOZE_MAX_MINUTES {
+        return Err(format!("Snooze can last at most {} minutes (got {})", SNOOZE_MAX_MINUTES, minutes));
+    }
+    if minutes == 0 {
+        end_snooze(app_handle, state);
+        return Ok(());
+    }
+    let window = overlay_window(app_handle)?;
+    let duration = Duration::from_secs(u64::from(minutes) * 60);
+    {
+        let mut state_guard = state.lock().unwrap();
+        state_guard.snoozed_until = Some(Instant::now() + duration);
+        set_overlay_visible(&window, &mut state_guard, false, "snoozed")?;
+    }
+    set_snooze_tray_title(app_handle, true);
+    println!("[Rust] Overlay snoozed for {} minutes", minutes);
+    let ends_at = (chrono::Local::now() + chrono::Duration::minutes(minutes.into())).to_rfc3339();
+    app_handle
+        .emit_all("snooze-started", SnoozeStartedEvent { minutes, ends_at })
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit snooze-started: {}", e));
+
+    let (app_handle, state) = (app_handle.clone(), state.clone());
+    tauri::async_runtime::spawn(async move {
+        sleep(duration).await;
+        // A later snooze moved the end, or the snooze was already ended
+        let expired = state.lock().unwrap().snoozed_until.is_some_and(|until| until <= Instant::now());
+        if expired {
+            end_snooze(&app_handle, &state);
+        }
+    });
+    Ok(())
+}
+
+fn end_snooze(app_handle: &AppHandle, state: &SharedState) {
+    if state.lock().unwrap().snoozed_until.take().is_none() {
+        return;
+    }
+    set_snooze_tray_title(app_handle, false);
+    println!("[Rust] Snooze ended");
+    app_handle
+        .emit_all("snooze-ended", ())
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit snooze-ended: {}", e));
+}
+
+fn set_snooze_tray_title(app_handle: &AppHandle, snoozed: bool) {
+    let title = if snoozed { "End snooze".to_string() } else { format!("Snooze {} min", TRAY_SNOOZE_MINUTES) };
+    app_handle
+        .tray_handle()
+        .get_item("snooze")
+        .set_title(title)
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to update tray menu: {}", e));
+}
+
+// How long a page may take from loading to calling frontend_ready before the error page is shown
+const UI_LOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Called by each page once its UI is up
+#[tauri::command]
+fn frontend_ready(window: Window, page_loads: tauri::State<'_, ui_fallback::PageLoads>) -> Result<(), String> {
+    page_loads.mark_ready(window.label());
+    Ok(())
+}
+
+fn watch_page_load(window: Window, url: String) {
+    let generation = window.state::<ui_fallback::PageLoads>().loaded(window.label());
+    tauri::async_runtime::spawn(async move {
+        sleep(UI_LOAD_TIMEOUT).await;
+        if !window.state::<ui_fallback::PageLoads>().is_stuck(window.label(), generation) {
+            return;
+        }
+        eprintln!("[Rust] UI of window '{}' didn't start within {:?} ({}), showing the error page", window.label(), UI_LOAD_TIMEOUT, url);
+        window
+            .eval(&ui_fallback::fallback_script(&url))
+            .unwrap_or_else(|e| eprintln!("[Rust] Failed to show the error page: {}", e));
+        if is_overlay_window(&window) {
+            // A click-through overlay would hide the message behind the windows below it
+            set_click_through(&window, false);
+            let state = window.state::<SharedState>();
+            set_overlay_visible(&window, &mut state.lock().unwrap(), true, "load-failed")
+                .unwrap_or_else(|e| eprintln!("[Rust] Failed to show window: {}", e));
+        }
+    });
+}
+
+// Async so the window is created off the main thread (sync commands deadlock on Windows)
+#[tauri::command]
+async fn open_settings(app_handle: AppHandle) -> Result<(), String> {
+    open_settings_window(&app_handle)
+}
+
+fn open_settings_window(app_handle: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_window("settings") {
+        window.show().map_err(|e| e.to_string())?;
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    tauri::WindowBuilder::new(app_handle, "settings", tauri::WindowUrl::App("settings.html".into()))
+        .title("Gaja Overlay Settings")
+        .inner_size(520.0, 640.0)
+        .build()
+        .map_err(|e| format!("Failed to open settings window: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_status(
+    app_handle: AppHandle,
+    status: String,
+    text: String,
+    is_listening: bool,
+    is_speaking: bool,
+    wake_word_detected: bool,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    let display_state = Status::from_legacy(&status, &text, is_listening, is_speaking, wake_word_detected);
+    {
+        let mut overlay_state = state.lock().unwrap();
+        overlay_state.status = status.clone();
+        overlay_state.text = text.clone();
+        overlay_state.partial = false;
+        overlay_state.is_listening = is_listening;
+        overlay_state.is_speaking = is_speaking;
+        overlay_state.wake_word_detected = wake_word_detected;
+        overlay_state.state = display_state;
+    }
+
+    let payload = serde_json::json!({
+        "status": status,
+        "state": display_state,
+        "text": text,
+        "partial": false,
+        "is_listening": is_listening,
+        "is_speaking": is_speaking,
+        "wake_word_detected": wake_word_detected
+    });
+    raw_log::emitted("status-update", &payload);
+    overlay_window(&app_handle)?.emit("status-update", payload).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// Drives the overlay into a given state without the client (for QA). Always available in
+// debug builds; release builds need `debug_commands` in the settings.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn set_debug_state(
+    app_handle: AppHandle,
+    status: String,
+    text: String,
+    is_listening: bool,
+    is_speaking: bool,
+    wake_word_detected: bool,
+    visible: bool,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    if !cfg!(debug_assertions) && !settings.lock().unwrap().debug_commands {
+        return Err("set_debug_state is disabled, enable debug_commands in the settings".to_string());
+    }
+    println!("[Rust] Forcing debug state: status='{}', text='{}', visible={}", status, text, visible);
+
+    let payload = serde_json::json!({
+        "status": status,
+        "text": text,
+        "is_listening": is_listening,
+        "is_speaking": is_speaking,
+        "wake_word_detected": wake_word_detected,
+    });
+    process_status_data(payload, app_handle.clone(), state.inner().clone());
+
+    // Visibility is forced, whatever the payload would normally imply
+    let window = overlay_window(&app_handle)?;
+    set_overlay_visible(&window, &mut state.lock().unwrap(), visible, "debug")
+}
+
+// How long the overlay stays clickable when set_interactive doesn't specify a duration
+const DEFAULT_INTERACTIVE_DURATION_MS: u64 = 15000;
+
+#[tauri::command]
+async fn set_interactive(
+    app_handle: AppHandle,
+    enabled: bool,
+    duration_ms: Option<u64>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    let window = overlay_window(&app_handle)?;
+    let generation = {
+        let mut overlay_state = state.lock().unwrap();
+        overlay_state.interactive = enabled;
+        overlay_state.interactive_generation += 1;
+        overlay_state.interactive_generation
+    };
+    set_click_through(&window, !enabled);
+
+    if enabled {
+        let duration = Duration::from_millis(duration_ms.unwrap_or(DEFAULT_INTERACTIVE_DURATION_MS));
+        println!("[Rust] Overlay interactive for {:?}", duration);
+        let state = state.inner().clone();
+        tauri::async_runtime::spawn(async move {
+            sleep(duration).await;
+            let expired = {
+                let mut overlay_state = state.lock().unwrap();
+                // Only revert if set_interactive wasn't called again in the meantime
+                if overlay_state.interactive && overlay_state.interactive_generation == generation {
+                    overlay_state.interactive = false;
+                    true
+                } else {
+                    false
+                }
+            };
+            if expired {
+                println!("[Rust] Interactive mode expired, restoring click-through");
+                set_click_through(&window, true);
+            }
+        });
+    } else {
+        println!("[Rust] Overlay interactive mode disabled");
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_state(state: tauri::State<Arc<Mutex<OverlayState>>>) -> Result<OverlayState, String> { // Ensure State is tauri::State
+    Ok(state.inner().lock().unwrap().clone())
+}
+
+// Snapshot for test harnesses, see snapshot::FullState; restore_state puts it back
+#[tauri::command]
+fn get_full_state(state: tauri::State<'_, SharedState>) -> Result<snapshot::FullState, String> {
+    Ok(snapshot::FullState::capture(&state.lock().unwrap()))
+}
+
+#[tauri::command]
+async fn restore_state(
+    snapshot: snapshot::FullState,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>,
+) -> Result<(), String> {
+    let language = {
+        let settings = settings.lock().unwrap();
+        if !cfg!(debug_assertions) && !settings.debug_commands {
+            return Err("restore_state is disabled, enable debug_commands in the settings".to_string());
+        }
+        settings.voice.language.clone()
+    };
+    let window = overlay_window(&app_handle)?;
+    let mut state_guard = state.lock().unwrap();
+    let visible = snapshot.visible;
+    snapshot.apply(&mut state_guard);
+    let payload = current_status_update(&state_guard, &language);
+    emit_status_update(&window, &state, &mut state_guard, payload);
+    set_overlay_visible(&window, &mut state_guard, visible, "restored")?;
+    println!("[Rust] Overlay state restored (visible={})", visible);
+    Ok(())
+}
+
+// Logs every raw payload from the client and every status event sent to the frontend.
+// Not saved, so conversation text only ends up in the log while someone is debugging.
+#[tauri::command]
+fn set_raw_logging(enabled: bool) -> Result<(), String> {
+    raw_log::set_enabled(enabled);
+    println!("[Rust] Raw message logging {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Records payloads from the client to `path`, with their timing, until stop_session_recording.
+// Like set_debug_state, release builds need `debug_commands`; the file has conversation text.
+#[tauri::command]
+fn record_session(
+    path: String,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    if !cfg!(debug_assertions) && !settings.lock().unwrap().debug_commands {
+        return Err("record_session is disabled, enable debug_commands in the settings".to_string());
+    }
+    let mut state_guard = state.lock().unwrap();
+    if let Some(recorder) = &state_guard.session_recording {
+        return Err(format!("Already recording to {}", recorder.path().display()));
+    }
+    state_guard.session_recording = Some(session::Recorder::new(path.clone().into(), Instant::now()));
+    println!("[Rust] Recording session to {}", path);
+    Ok(())
+}
+
+// Writes the recording and returns the number of payloads in it
+#[tauri::command]
+fn stop_session_recording(state: tauri::State<'_, SharedState>) -> Result<usize, String> {
+    let recorder = state.lock().unwrap().session_recording.take().ok_or("No session is being recorded")?;
+    let count = recorder.save()?;
+    println!("[Rust] Recorded {} payloads to {}", count, recorder.path().display());
+    Ok(count)
+}
+
+// Feeds a recorded session through process_status_data at the recorded pace and returns the
+// number of payloads once done. Payloads from the client are ignored meanwhile.
+#[tauri::command]
+async fn replay_session(
+    path: String,
+    app_handle: AppHandle,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<usize, String> {
+    if !cfg!(debug_assertions) && !settings.lock().unwrap().debug_commands {
+        return Err("replay_session is disabled, enable debug_commands in the settings".to_string());
+    }
+    let contents = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let recorded = session::Session::parse(&contents)?;
+    {
+        let mut state_guard = state.lock().unwrap();
+        if state_guard.replaying_session {
+            return Err("A session is already being replayed".to_string());
+        }
+        state_guard.replaying_session = true;
+    }
+    println!("[Rust] Replaying {} payloads from {}", recorded.payloads.len(), path);
+    let started = tokio::time::Instant::now();
+    for payload in recorded.payloads.iter() {
+        tokio::time::sleep_until(started + Duration::from_millis(payload.at_ms)).await;
+        process_status_data(payload.payload.clone(), app_handle.clone(), state.inner().clone());
+    }
+    state.lock().unwrap().replaying_session = false;
+    println!("[Rust] Replay of {} finished", path);
+    Ok(recorded.payloads.len())
+}
+
+#[tauri::command]
+fn get_status_history(state: tauri::State<'_, SharedState>) -> Result<Vec<StatusHistoryEntry>, String> {
+    Ok(state.lock().unwrap().status_history.iter().cloned().collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CheckResult {
+    Pass,
+    Fail,
+    // Not applicable to this platform or build
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+struct SelfTestCheck {
+    name: &'static str,
+    result: CheckResult,
+    detail: String,
+}
+
+impl SelfTestCheck {
+    fn from_result(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => SelfTestCheck { name, result: CheckResult::Pass, detail },
+            Err(detail) => SelfTestCheck { name, result: CheckResult::Fail, detail },
+        }
+    }
+
+    fn skipped(name: &'static str, detail: &str) -> Self {
+        SelfTestCheck { name, result: CheckResult::Skipped, detail: detail.to_string() }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SelfTestReport {
+    overlay_version: String,
+    os: &'static str,
+    passed: bool,
+    checks: Vec<SelfTestCheck>,
+}
+
+// Runs the separate connection, audio, settings and window checks in one go, for bug reports
+#[tauri::command]
+async fn run_self_test(app_handle: AppHandle, client: tauri::State<'_, reqwest::Client>) -> Result<SelfTestReport, String> {
+    let window = overlay_window(&app_handle)?;
+    let mut checks = vec![
+        SelfTestCheck::from_result("settings_dir", settings::check_settings_dir()),
+        SelfTestCheck::from_result("settings_file", settings::check_settings_file()),
+    ];
+
+    let mut errors = Vec::new();
+    let ports = app_handle.state::<settings::SharedSettings>().lock().unwrap().candidate_ports();
+    let mut server = Err(String::new());
+    for port in ports {
+        if let Some(base_url) = probe_port_addresses(&client, &app_handle, port, None, &mut errors).await {
+            server = Ok(format!("Client answering at {}", base_url));
+            break;
+        }
+    }
+    checks.push(SelfTestCheck::from_result(
+        "server",
+        server.map_err(|_| errors.iter().map(ProbeError::message).collect::<Vec<_>>().join("; ")),
+    ));
+
+    checks.push(match audio::check_default_devices() {
+        Some(result) => SelfTestCheck::from_result("audio_devices", result),
+        None => SelfTestCheck::skipped("audio_devices", "Built without audio support"),
+    });
+
+    let monitors = window.available_monitors().map_err(|e| e.to_string()).and_then(|monitors| {
+        let primary = window.primary_monitor().map_err(|e| e.to_string())?;
+        match (monitors.len(), primary) {
+            (0, _) => Err("No monitors detected".to_string()),
+            (count, Some(primary)) => Ok(format!("{} monitor(s), primary {:?}", count, primary.name())),
+            (count, None) => Err(format!("{} monitor(s) but no primary monitor", count)),
+        }
+    });
+    checks.push(SelfTestCheck::from_result("monitors", monitors));
+    checks.push(click_through_check(&window));
+
+    let passed = checks.iter().all(|check| check.result != CheckResult::Fail);
+    println!("[Rust] Self-test {}", if passed { "passed" } else { "found problems" });
+    Ok(SelfTestReport {
+        overlay_version: app_handle.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        passed,
+        checks,
+    })
+}
+
+// Whether the window really lets clicks through (WS_EX_TRANSPARENT), not what was requested
+fn applied_click_through(window: &Window) -> Result<bool, String> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{GetWindowLongPtrW, GWL_EXSTYLE, WS_EX_TRANSPARENT};
+
+        let hwnd = get_hwnd(window)?;
+        let ex_style = unsafe { GetWindowLongPtrW(hwnd, GWL_EXSTYLE) };
+        Ok(ex_style & WS_EX_TRANSPARENT as isize != 0)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = window;
+        Err("Click-through is only implemented on Windows".to_string())
+    }
+}
+
+#[tauri::command]
+async fn is_click_through(app_handle: AppHandle) -> Result<bool, String> {
+    applied_click_through(&overlay_window(&app_handle)?)
+}
+
+// Compares the window's actual click-through style with what the settings ask for
+fn click_through_check(window: &Window) -> SelfTestCheck {
+    #[cfg(target_os = "windows")]
+    {
+        let enabled = window.state::<settings::SharedSettings>().lock().unwrap().overlay.click_through;
+        let expected = enabled && !window.state::<SharedState>().lock().unwrap().interactive;
+        let result = applied_click_through(window).and_then(|applied| {
+            if applied == expected {
+                Ok(format!("Click-through {}", if applied { "on" } else { "off" }))
+            } else {
+                Err(format!("Click-through should be {} but is {}", if expected { "on" } else { "off" }, if applied { "on" } else { "off" }))
+            }
+        });
+        SelfTestCheck::from_result("click_through", result)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = window;
+        SelfTestCheck::skipped("click_through", "Click-through is only implemented on Windows")
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Diagnostics {
+    connected: bool,
+    port: Option<u16>,
+    // Set with set_active_port, None while ports are discovered
+    forced_port: Option<u16>,
+    state: Status,
+    visible: bool,
+    notification_queue_length: usize,
+    voice_sync_pending: bool,
+    audio_sync_pending: bool,
+    last_error: Option<String>,
+    unexpected_response: Option<status::UnexpectedResponse>,
+    monitor_warning: Option<String>,
+    dnd_active: bool,
+    // Time left until the overlay may show again, None while not snoozed
+    snooze_remaining_ms: Option<u64>,
+    poll_task_restarts: u32,
+    status_updates: u64,
+    reconnects: u64,
+    transport: Option<&'static str>,
+    // Settings.transport: "auto" or the forced transport
+    transport_setting: String,
+    // RFC 3339, local time
+    next_briefing_at: Option<String>,
+    // When settings were last written to disk, RFC 3339
+    last_settings_flush_at: Option<String>,
+}
+
+#[tauri::command]
+fn get_diagnostics(
+    state: tauri::State<'_, SharedState>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    writer: tauri::State<'_, settings::SettingsWriter>
+) -> Result<Diagnostics, String> {
+    let overlay_state = state.lock().unwrap();
+    Ok(Diagnostics {
+        connected: overlay_state.connected,
+        port: overlay_state.last_good_port,
+        forced_port: overlay_state.forced_port,
+        state: overlay_state.state,
+        visible: overlay_state.visible,
+        notification_queue_length: overlay_state.notification_queue.len(),
+        voice_sync_pending: overlay_state.voice_sync_pending,
+        audio_sync_pending: overlay_state.audio_sync_pending,
+        last_error: overlay_state.last_error.clone(),
+        unexpected_response: overlay_state.unexpected_response.clone(),
+        monitor_warning: overlay_state.monitor_warning.clone(),
+        dnd_active: overlay_state.dnd_active,
+        snooze_remaining_ms: overlay_state
+            .snoozed_until
+            .map(|until| until.saturating_duration_since(Instant::now()).as_millis() as u64),
+        poll_task_restarts: overlay_state.poll_task_restarts,
+        status_updates: overlay_state.status_updates,
+        reconnects: overlay_state.reconnects,
+        transport: overlay_state.transport,
+        transport_setting: settings.lock().unwrap().transport.clone(),
+        next_briefing_at: overlay_state.next_briefing_at.map(|at| at.to_rfc3339()),
+        last_settings_flush_at: writer.last_flush().map(|at| at.to_rfc3339()),
+    })
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+// Everything a bug report needs in one zip. Credentials in the client URLs are always
+// redacted; with `redact_text` what was said is taken out of the status history too.
+#[tauri::command]
+async fn export_diagnostics_bundle(
+    path: String,
+    redact_text: bool,
+    app_handle: AppHandle,
+    client: tauri::State<'_, reqwest::Client>,
+) -> Result<(), String> {
+    let settings = bundle::redact_settings(&app_handle.state::<settings::SharedSettings>().lock().unwrap());
+    let mut history: Vec<StatusHistoryEntry> =
+        app_handle.state::<SharedState>().lock().unwrap().status_history.iter().cloned().collect();
+    if redact_text {
+        history.iter_mut().for_each(|entry| bundle::redact_conversation(&mut entry.payload));
+    }
+    let mut diagnostics = get_diagnostics(app_handle.state(), app_handle.state(), app_handle.state())?;
+    if redact_text {
+        // Can quote a status payload
+        diagnostics.unexpected_response = None;
+    }
+    let self_test = run_self_test(app_handle.clone(), client).await?;
+
+    let files = [
+        ("settings.json", to_json(&settings)?),
+        ("status_history.json", to_json(&history)?),
+        ("diagnostics.json", to_json(&diagnostics)?),
+        ("self_test.json", to_json(&self_test)?),
+    ];
+    bundle::write_bundle(std::path::Path::new(&path), &files)?;
+    println!("[Rust] Diagnostics bundle written to {}", path);
+    Ok(())
+}
+
+// Timeouts for requests to the client. SSE streams are long-lived, so they only get the
+// request timeout for the initial response and are then dropped if no data arrives for a while.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// The client sends a heartbeat every 30 seconds; allow for missing two of them
+const SSE_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+fn build_http_client(allow_invalid_certs: bool) -> reqwest::Client {
+    if allow_invalid_certs {
+        println!("[Rust] allow_invalid_certs is set, TLS certificates will not be verified");
+    }
+    // No client-wide total timeout: it would also cut off the SSE stream body.
+    // Compressed responses (Accept-Encoding: gzip, br) save bandwidth to a client on the LAN.
+    reqwest::Client::builder()
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .gzip(true)
+        .brotli(true)
+        .danger_accept_invalid_certs(allow_invalid_certs)
+        .build()
+        .unwrap_or_else(|e| {
+            eprintln!("[Rust] Failed to build HTTP client: {}, using defaults", e);
+            reqwest::Client::new()
+        })
+}
+
+// Names certificate problems explicitly; reqwest only says "error sending request" at the top
+fn describe_request_error(error: &reqwest::Error) -> String {
+    let mut details = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        details = format!("{}: {}", details, cause);
+        source = cause.source();
+    }
+    if details.to_lowercase().contains("certificate") {
+        format!("TLS certificate error ({}). For a self-signed certificate, set allow_invalid_certs in the settings.", details)
+    } else {
+        details
+    }
+}
+
+fn record_error(state: &Arc<Mutex<OverlayState>>, error: String) {
+    state.lock().unwrap().last_error = Some(error);
+}
+
+// Why a base URL didn't work. Something that answers with the wrong thing (a proxy error
+// page, another web server) is reported separately from nothing answering at all.
+enum ProbeError {
+    Unreachable(String),
+    Unexpected(status::UnexpectedResponse),
+}
+
+impl ProbeError {
+    fn message(&self) -> String {
+        match self {
+            ProbeError::Unreachable(error) => error.clone(),
+            ProbeError::Unexpected(response) => response.describe(),
+        }
+    }
+}
+
+// Returns the status the client answered with
+async fn probe_port(client: &reqwest::Client, base_url: &str, status_path: &str) -> Result<serde_json::Value, ProbeError> {
+    let test_url = format!("{}{}", base_url, status_path);
+    println!("[Rust] Testing connection to CLIENT at {}", base_url);
+    let response = client
+        .get(&test_url)
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| {
+            let error = format!("Connection to client at {} failed: {}", base_url, describe_request_error(&e));
+            println!("[Rust] {}", error);
+            ProbeError::Unreachable(error)
+        })?;
+    let status = response.status();
+    let final_url = response.url().to_string();
+    let body = response.text().await.unwrap_or_default();
+    if let (true, Ok(status)) = (status.is_success(), serde_json::from_str::<serde_json::Value>(&body)) {
+        if final_url != test_url {
+            println!("[Rust] {} redirects to {}, consider setting host to match", test_url, final_url);
+        }
+        return Ok(status);
+    }
+    let error = ProbeError::Unexpected(status::UnexpectedResponse::new(test_url, status.as_u16(), &body, &final_url));
+    println!("[Rust] {}", error.message());
+    Err(error)
+}
+
+// Tries every address of `port` (`preferred` first), returns the first base URL that answers
+async fn probe_port_addresses(
+    client: &reqwest::Client,
+    app_handle: &AppHandle,
+    port: u16,
+    preferred: Option<&str>,
+    errors: &mut Vec<ProbeError>,
+) -> Option<String> {
+    let (mut base_urls, status_path) = {
+        let shared_settings = app_handle.state::<settings::SharedSettings>();
+        let settings_guard = shared_settings.lock().unwrap();
+        (settings_guard.client_base_urls(port), settings_guard.status_path.clone())
+    };
+    if app_handle.state::<SharedState>().lock().unwrap().connection_fault_active() {
+        let error = format!("Connection to client on port {} failed: injected fault", port);
+        println!("[Rust] {}", error);
+        errors.push(ProbeError::Unreachable(error));
+        return None;
+    }
+    if let Some(index) = preferred.and_then(|url| base_urls.iter().position(|u| u == url)) {
+        let preferred = base_urls.remove(index);
+        base_urls.insert(0, preferred);
+    }
+    for base_url in base_urls {
+        match probe_port(client, &base_url, &status_path).await {
+            Ok(_) => return Some(base_url),
+            Err(e) => errors.push(e),
+        }
+    }
+    None
+}
+
+// Try the last known good port first, then scan the configured ports (only the port from
+// set_active_port, if there is one). Returns the port and the base URL that answered on it.
+async fn find_working_port(client: &reqwest::Client, app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>) -> Option<(u16, String)> {
+    let mut errors = Vec::new();
+    let (cached_port, cached_base_url, forced_port) = {
+        let state_guard = state.lock().unwrap();
+        (state_guard.last_good_port, state_guard.last_good_base_url.clone(), state_guard.forced_port)
+    };
+    let cached_port = cached_port.filter(|port| forced_port.is_none_or(|forced| forced == *port));
+    if let Some(port) = cached_port {
+        // The address that worked last time is probed first
+        let cached = probe_port_addresses(client, app_handle, port, cached_base_url.as_deref(), &mut errors).await;
+        if let Some(base_url) = cached {
+            println!("[Rust] Reusing last known CLIENT port {} at {}", port, base_url);
+            state.lock().unwrap().last_good_base_url = Some(base_url.clone());
+            return Some((port, base_url));
+        }
+        println!("[Rust] Last known CLIENT port {} is not responding, scanning all ports", port);
+    }
+
+    // ONLY try client ports - NEVER connect to main server (8001)
+    let ports = match forced_port {
+        Some(port) => vec![port],
+        None => app_handle.state::<settings::SharedSettings>().lock().unwrap().candidate_ports(),
+    };
+    for port in ports {
+        if Some(port) == cached_port {
+            continue;
+        }
+        if let Some(base_url) = probe_port_addresses(client, app_handle, port, None, &mut errors).await {
+            println!("[Rust] Found working CLIENT port {} at {}", port, base_url);
+            let mut state_guard = state.lock().unwrap();
+            state_guard.last_good_port = Some(port);
+            state_guard.last_good_base_url = Some(base_url.clone());
+            return Some((port, base_url));
+        }
+    }
+    if !errors.is_empty() {
+        let messages: Vec<String> = errors.iter().map(ProbeError::message).collect();
+        record_error(state, messages.join("; "));
+    }
+    // The last thing that answered wrongly, so the UI can say it's not a network problem
+    state.lock().unwrap().unexpected_response = errors.into_iter().rev().find_map(|error| match error {
+        ProbeError::Unexpected(response) => Some(response),
+        ProbeError::Unreachable(_) => None,
+    });
+    None
+}
+
+// The client's save endpoint replaces its whole settings object, so fetch the current
+// settings and merge the fields of `section` ("voice", "audio") in before posting them back
+async fn push_client_settings(client: &reqwest::Client, base_url: &str, section: &str, values: &impl Serialize) -> Result<serde_json::Value, String> {
+    let mut current: serde_json::Value = client
+        .get(format!("{}/api/current_settings", base_url))
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach client: {}", describe_request_error(&e)))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid settings from client: {}", e))?;
+
+    let client_settings = current
+        .as_object_mut()
+        .ok_or("Client returned settings that are not an object")?;
+    let section_entry = client_settings.entry(section).or_insert_with(|| serde_json::json!({}));
+    if !section_entry.is_object() {
+        *section_entry = serde_json::json!({});
+    }
+    if let (Some(target), serde_json::Value::Object(fields)) =
+        (section_entry.as_object_mut(), serde_json::to_value(values).map_err(|e| e.to_string())?)
+    {
+        target.extend(fields);
+    }
+
+    let response = client
+        .post(format!("{}/api/save_settings", base_url))
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .json(&serde_json::json!({ "settings": current }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send {} settings: {}", section, describe_request_error(&e)))?;
+    let status = response.status();
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from client: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Client rejected {} settings ({}): {}", section, status, body));
+    }
+    // A failed save still answers 200, with "success": false
+    if body.get("success").and_then(|success| success.as_bool()) != Some(true) {
+        return Err(format!("Client failed to save {} settings: {}", section, body));
+    }
+    Ok(body)
+}
+
+#[derive(Debug, Serialize)]
+struct ClientSettingsUpdate {
+    // The running client accepted the new settings
+    synced: bool,
+    server_response: Option<serde_json::Value>,
+    // Saved locally only; sent again on the next connection
+    sync_pending: bool,
+    error: Option<String>,
+}
+
+#[tauri::command]
+async fn update_voice_settings(
+    voice: settings::VoiceSettings,
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    writer: tauri::State<'_, settings::SettingsWriter>,
+    state: tauri::State<'_, SharedState>
+) -> Result<ClientSettingsUpdate, String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let updated = settings_guard.with_voice(voice.clone())?;
+        writer.write_now(&updated)?;
+        *settings_guard = updated;
+    }
+    Ok(sync_client_settings(&client, &state, "voice", &voice, |state| &mut state.voice_sync_pending).await)
+}
+
+// Saves the devices the user just tested as the ones the client should use. Both ids must
+// still resolve, so a device unplugged since get_audio_devices isn't saved; an empty id
+// selects the system default.
+#[tauri::command]
+async fn apply_audio_selection(
+    input_id: String,
+    output_id: String,
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    writer: tauri::State<'_, settings::SettingsWriter>,
+    state: tauri::State<'_, SharedState>
+) -> Result<ClientSettingsUpdate, String> {
+    let audio = settings::AudioSettings { input_device: input_id, output_device: output_id };
+    audio.check_devices(audio::resolve_audio_device)?;
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.audio = audio.clone();
+        writer.write_now(&updated)?;
+        *settings_guard = updated;
+    }
+    println!("[Rust] Audio devices set to input '{}', output '{}'", audio.input_device, audio.output_device);
+    Ok(sync_client_settings(&client, &state, "audio", &audio, |state| &mut state.audio_sync_pending).await)
+}
+
+// Sends saved settings to the client, or marks them for the next connection with `pending`
+async fn sync_client_settings(
+    client: &reqwest::Client,
+    state: &SharedState,
+    section: &str,
+    values: &impl Serialize,
+    pending: fn(&mut OverlayState) -> &mut bool
+) -> ClientSettingsUpdate {
+    let base_url = state.lock().unwrap().last_good_base_url.clone();
+    let result = match base_url {
+        Some(base_url) => push_client_settings(client, &base_url, section, values).await,
+        None => Err("Client is not connected".to_string()),
+    };
+    *pending(&mut state.lock().unwrap()) = result.is_err();
+
+    match result {
+        Ok(response) => {
+            println!("[Rust] {} settings updated on client: {}", section, response);
+            ClientSettingsUpdate { synced: true, server_response: Some(response), sync_pending: false, error: None }
+        }
+        Err(e) => {
+            eprintln!("[Rust] {} settings saved locally, client sync pending: {}", section, e);
+            ClientSettingsUpdate { synced: false, server_response: None, sync_pending: true, error: Some(e) }
+        }
+    }
+}
+
+// Changes voice.language: overlay labels are redrawn right away, the client gets it like any
+// other voice setting, and "language-changed" lets the frontend switch its own strings
+#[tauri::command]
+async fn set_language(
+    language: String,
+    app_handle: AppHandle,
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<ClientSettingsUpdate, String> {
+    let language = language.trim().to_string();
+    if !status::is_supported_language(&language) {
+        return Err(format!("Unsupported language '{}', expected one of {:?}", language, status::supported_languages()));
+    }
+    let voice = {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.voice.language = language.clone();
+        app_handle.state::<settings::SettingsWriter>().write_now(&updated)?;
+        *settings_guard = updated;
+        settings_guard.voice.clone()
+    };
+    println!("[Rust] Language set to {}", language);
+
+    let window = overlay_window(&app_handle)?;
+    {
+        let mut state_guard = state.lock().unwrap();
+        let payload = current_status_update(&state_guard, &language);
+        emit_status_update(&window, &state, &mut state_guard, payload);
+    }
+    app_handle
+        .emit_all("language-changed", &language)
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit language-changed: {}", e));
+    Ok(sync_client_settings(&client, &state, "voice", &voice, |state| &mut state.voice_sync_pending).await)
+}
+
+// Retries settings pushes that failed while the client was unreachable
+async fn sync_pending_client_settings(client: &reqwest::Client, base_url: &str, app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>) {
+    let (voice_pending, audio_pending) = {
+        let state_guard = state.lock().unwrap();
+        (state_guard.voice_sync_pending, state_guard.audio_sync_pending)
+    };
+    let (voice, audio) = {
+        let shared_settings = app_handle.state::<settings::SharedSettings>();
+        let settings_guard = shared_settings.lock().unwrap();
+        (settings_guard.voice.clone(), settings_guard.audio.clone())
+    };
+    if voice_pending {
+        match push_client_settings(client, base_url, "voice", &voice).await {
+            Ok(_) => {
+                println!("[Rust] Pending voice settings synced to client");
+                state.lock().unwrap().voice_sync_pending = false;
+            }
+            Err(e) => eprintln!("[Rust] Voice settings still not synced: {}", e),
+        }
+    }
+    if audio_pending {
+        match push_client_settings(client, base_url, "audio", &audio).await {
+            Ok(_) => {
+                println!("[Rust] Pending audio settings synced to client");
+                state.lock().unwrap().audio_sync_pending = false;
+            }
+            Err(e) => eprintln!("[Rust] Audio settings still not synced: {}", e),
+        }
+    }
+}
+
+// Response of the client's /api/info endpoint. Every field is optional so older or
+// partial implementations still parse.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ServerInfo {
+    version: Option<String>,
+    // e.g. ["sse", "polling"]
+    transports: Vec<String>,
+    features: Vec<String>,
+}
+
+impl ServerInfo {
+    // Servers that don't list their transports are assumed to support everything
+    fn supports_transport(&self, transport: &str) -> bool {
+        self.transports.is_empty() || self.transports.iter().any(|t| t == transport)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ServerInfoReport {
+    overlay_version: &'static str,
+    server: ServerInfo,
+}
+
+async fn fetch_server_info(client: &reqwest::Client, base_url: &str) -> Result<ServerInfo, String> {
+    let response = client
+        .get(format!("{}/api/info", base_url))
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach client: {}", describe_request_error(&e)))?;
+    if !response.status().is_success() {
+        return Err(format!("Client /api/info returned status: {}", response.status()));
+    }
+    response
+        .json::<ServerInfo>()
+        .await
+        .map_err(|e| format!("Invalid /api/info response: {}", e))
+}
+
+#[tauri::command]
+async fn get_server_info(
+    client: tauri::State<'_, reqwest::Client>,
+    state: tauri::State<'_, SharedState>
+) -> Result<ServerInfoReport, String> {
+    let base_url = state.lock().unwrap().last_good_base_url.clone().ok_or("Not connected to the client")?;
+    let info = fetch_server_info(&client, &base_url).await?;
+    state.lock().unwrap().server_info = Some(info.clone());
+    Ok(ServerInfoReport {
+        overlay_version: env!("CARGO_PKG_VERSION"),
+        server: info,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct WakeWordsResponse {
+    wake_words: Vec<String>,
+}
+
+async fn fetch_wake_words(client: &reqwest::Client, base_url: &str) -> Result<Vec<String>, String> {
+    let response = client
+        .get(format!("{}/api/wake_words", base_url))
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach client: {}", describe_request_error(&e)))?;
+    if !response.status().is_success() {
+        return Err(format!("Client /api/wake_words returned status: {}", response.status()));
+    }
+    let response = response
+        .json::<WakeWordsResponse>()
+        .await
+        .map_err(|e| format!("Invalid /api/wake_words response: {}", e))?;
+    let wake_words: Vec<String> = response
+        .wake_words
+        .into_iter()
+        .map(|word| word.trim().to_string())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if wake_words.is_empty() {
+        return Err("Client listed no wake words".to_string());
+    }
+    Ok(wake_words)
+}
+
+// Wake words the client's engine supports, for the settings window to choose from. Without a
+// list from the client (older client, not connected), the configured wake word is the only option.
+#[tauri::command]
+async fn get_available_wake_words(
+    client: tauri::State<'_, reqwest::Client>,
+    state: tauri::State<'_, SharedState>,
+    settings: tauri::State<'_, settings::SharedSettings>
+) -> Result<Vec<String>, String> {
+    let (cached, base_url) = {
+        let state_guard = state.lock().unwrap();
+        (state_guard.available_wake_words.clone(), state_guard.last_good_base_url.clone())
+    };
+    if let Some(wake_words) = cached {
+        return Ok(wake_words);
+    }
+    let fetched = match base_url {
+        Some(base_url) => fetch_wake_words(&client, &base_url).await,
+        None => Err("Not connected to the client".to_string()),
+    };
+    match fetched {
+        Ok(wake_words) => {
+            state.lock().unwrap().available_wake_words = Some(wake_words.clone());
+            Ok(wake_words)
+        }
+        Err(e) => {
+            println!("[Rust] No wake word list from the client ({}), offering the configured one", e);
+            Ok(vec![settings.lock().unwrap().voice.wake_word.clone()])
+        }
+    }
+}
+
+// Kept short so quitting isn't held up when the client is down
+const SHUTDOWN_NOTIFY_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Tells the client the overlay is going away so it stops driving it. Blocks until the
+// request finishes or SHUTDOWN_NOTIFY_TIMEOUT passes.
+fn notify_overlay_shutdown(app_handle: &AppHandle, reason: &str) {
+    let base_url = {
+        let state = app_handle.state::<SharedState>();
+        let state_guard = state.lock().unwrap();
+        if !state_guard.connected {
+            return;
+        }
+        state_guard.last_good_base_url.clone()
+    };
+    let Some(base_url) = base_url else {
+        return;
+    };
+    let client = app_handle.state::<reqwest::Client>().inner().clone();
+    let request = client
+        .post(format!("{}/api/overlay/disconnect", base_url))
+        .timeout(SHUTDOWN_NOTIFY_TIMEOUT)
+        .json(&serde_json::json!({ "reason": reason }))
+        .send();
+    match tauri::async_runtime::block_on(request) {
+        Ok(response) => println!("[Rust] Client notified of overlay shutdown ({})", response.status()),
+        Err(e) => eprintln!("[Rust] Failed to notify client of overlay shutdown: {}", describe_request_error(&e)),
+    }
+}
+
+// Delay before reconnecting; doubled after every cycle that never received data
+const RECONNECT_DELAY_INITIAL: Duration = Duration::from_secs(5);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(60);
+// How often the connection loop checks the paused/reconnect flags (no network involved)
+const FLAG_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+// Failed connection cycles in a row before the overlay reports itself as disconnected
+const FAILED_CYCLES_BEFORE_DISCONNECTED: u32 = 3;
+
+async fn wait_for_paused(state: &Arc<Mutex<OverlayState>>, paused: bool) {
+    while state.lock().unwrap().paused != paused {
+        sleep(FLAG_CHECK_INTERVAL).await;
+    }
+}
+
+async fn wait_for_connection_fault(state: &Arc<Mutex<OverlayState>>) {
+    while !state.lock().unwrap().connection_fault_active() {
+        sleep(FLAG_CHECK_INTERVAL).await;
+    }
+}
+
+async fn wait_for_connection_change(state: &Arc<Mutex<OverlayState>>) {
+    loop {
+        {
+            let mut state_guard = state.lock().unwrap();
+            if state_guard.connection_changed {
+                state_guard.connection_changed = false;
+                return;
+            }
+        }
+        sleep(FLAG_CHECK_INTERVAL).await;
+    }
+}
+
+async fn wait_for_reconnect_request(state: &Arc<Mutex<OverlayState>>) {
+    loop {
+        {
+            let mut state_guard = state.lock().unwrap();
+            if state_guard.reconnect_requested {
+                state_guard.reconnect_requested = false;
+                return;
+            }
+        }
+        sleep(FLAG_CHECK_INTERVAL).await;
+    }
+}
+
+// Sent while waiting for the next connection attempt, once a second, so the frontend can
+// count down to it
+#[derive(Clone, Serialize)]
+struct ReconnectingEvent {
+    // Connection cycles failed in a row, 0 after a cycle that received data
+    attempt: u32,
+    delay_ms: u64,
+    // 0 when the attempt starts, also when reconnect_now cut the wait short
+    remaining_ms: u64,
+}
+
+const RECONNECT_COUNTDOWN_INTERVAL: Duration = Duration::from_secs(1);
+
+fn emit_reconnecting(app_handle: &AppHandle, attempt: u32, delay: Duration, remaining: Duration) {
+    let event = ReconnectingEvent { attempt, delay_ms: delay.as_millis() as u64, remaining_ms: remaining.as_millis() as u64 };
+    app_handle
+        .emit_all("reconnecting", event)
+        .unwrap_or_else(|e| eprintln!("Failed to emit reconnecting: {}", e));
+}
+
+// Waits `delay`, emitting the time left every RECONNECT_COUNTDOWN_INTERVAL
+async fn reconnect_countdown(app_handle: &AppHandle, attempt: u32, delay: Duration) {
+    let deadline = tokio::time::Instant::now() + delay;
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        emit_reconnecting(app_handle, attempt, delay, remaining);
+        if remaining.is_zero() {
+            return;
+        }
+        sleep(remaining.min(RECONNECT_COUNTDOWN_INTERVAL)).await;
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct ConnectionFailedEvent {
+    tried_ports: Vec<u16>,
+    last_error: Option<String>,
+    unexpected_response: Option<status::UnexpectedResponse>,
+    failed_attempts: u32,
+}
+
+// Makes the client look unreachable for `duration_ms`, to test reconnecting and backoff
+// without stopping it. The current connection is dropped and every port probe fails until
+// the time is up; 0 ends a fault early. Debug builds only.
+#[tauri::command]
+fn inject_connection_fault(duration_ms: u64, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    if !cfg!(debug_assertions) {
+        return Err("inject_connection_fault is only available in debug builds".to_string());
+    }
+    let until = Instant::now()
+        .checked_add(Duration::from_millis(duration_ms))
+        .ok_or_else(|| format!("Fault duration {}ms is too long", duration_ms))?;
+    state.lock().unwrap().connection_fault_until = Some(until);
+    println!("[Rust] Injected connection fault for {}ms", duration_ms);
+    Ok(())
+}
+
+// Saves Settings.transport and reconnects with it, dropping the current connection
+#[tauri::command]
+fn set_transport(
+    transport: String,
+    state: tauri::State<'_, SharedState>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    writer: tauri::State<'_, settings::SettingsWriter>
+) -> Result<(), String> {
+    {
+        let mut settings_guard = settings.lock().unwrap();
+        let mut updated = settings_guard.clone();
+        updated.transport = transport.clone();
+        updated.validate()?;
+        writer.write_now(&updated)?;
+        *settings_guard = updated;
+    }
+    println!("[Rust] Transport set to {}, reconnecting", transport);
+    state.lock().unwrap().connection_changed = true;
+    Ok(())
+}
+
+// Connects to `port` only, instead of the first configured port that answers, until called
+// with None. For two clients on one machine; not saved.
+#[tauri::command]
+fn set_active_port(port: Option<u16>, state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    if port == Some(0) {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    match port {
+        Some(port) => println!("[Rust] Active port set to {}, reconnecting", port),
+        None => println!("[Rust] Active port cleared, reconnecting"),
+    }
+    let mut state_guard = state.lock().unwrap();
+    state_guard.forced_port = port;
+    state_guard.connection_changed = true;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct CandidateServer {
+    port: u16,
+    // The address that answered, None if nothing did
+    base_url: Option<String>,
+    // From /api/info, if the client has it
+    version: Option<String>,
+    // The `status` field of its status response
+    status: Option<String>,
+    // The overlay is connected to this one
+    active: bool,
+    error: Option<String>,
+}
+
+// Probes every configured port (and the one from set_active_port), so the user can pick a
+// client when more than one is running
+#[tauri::command]
+async fn list_candidate_servers(
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<Vec<CandidateServer>, String> {
+    let (mut ports, status_path) = {
+        let settings_guard = settings.lock().unwrap();
+        (settings_guard.candidate_ports(), settings_guard.status_path.clone())
+    };
+    let (forced_port, connected_port) = {
+        let state_guard = state.lock().unwrap();
+        (state_guard.forced_port, state_guard.last_good_port.filter(|_| state_guard.connected))
+    };
+    if let Some(port) = forced_port.filter(|port| !ports.contains(port)) {
+        ports.push(port);
+    }
+
+    let mut servers = Vec::new();
+    for port in ports {
+        let base_urls = settings.lock().unwrap().client_base_urls(port);
+        let mut server = CandidateServer { port, base_url: None, version: None, status: None, active: connected_port == Some(port), error: None };
+        let mut errors = Vec::new();
+        for base_url in base_urls {
+            match probe_port(&client, &base_url, &status_path).await {
+                Ok(status) => {
+                    server.status = status::StatusPayload::parse(&status).ok().and_then(|payload| payload.status);
+                    server.version = fetch_server_info(&client, &base_url).await.ok().and_then(|info| info.version);
+                    server.base_url = Some(base_url);
+                    break;
+                }
+                Err(e) => errors.push(e.message()),
+            }
+        }
+        if server.base_url.is_none() {
+            server.error = Some(errors.join("; "));
+        }
+        servers.push(server);
+    }
+    Ok(servers)
+}
+
+// Stops waiting for the next reconnect attempt and retries immediately with the initial backoff
+#[tauri::command]
+fn reconnect_now(state: tauri::State<'_, SharedState>) -> Result<(), String> {
+    println!("[Rust] Reconnect requested");
+    state.lock().unwrap().reconnect_requested = true;
+    Ok(())
+}
+
+// Delay before the watchdog relaunches a connection loop that ended or panicked
+const POLL_TASK_RESTART_DELAY: Duration = Duration::from_secs(2);
+
+// Runs the connection loop and relaunches it if it ever ends or panics
+async fn supervise_poll_task(client: reqwest::Client, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
+    loop {
+        let task = tauri::async_runtime::spawn(poll_assistant_status(client.clone(), app_handle.clone(), state.clone()));
+        match task.await {
+            Ok(()) => eprintln!("[Rust] Connection loop ended unexpectedly, restarting in {:?}", POLL_TASK_RESTART_DELAY),
+            Err(e) => eprintln!("[Rust] Connection loop panicked ({}), restarting in {:?}", e, POLL_TASK_RESTART_DELAY),
+        }
+        // A panic while holding the state lock would otherwise take every later lock down with it
+        if state.is_poisoned() {
+            eprintln!("[Rust] Overlay state lock was poisoned, recovering");
+            state.clear_poison();
+        }
+        state.lock().unwrap().poll_task_restarts += 1;
+        sleep(POLL_TASK_RESTART_DELAY).await;
+    }
+}
+
+// Connection loop: pick a port and transport, run it until it fails, back off and repeat
+async fn poll_assistant_status(client: reqwest::Client, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
+    let mut reconnect_delay = RECONNECT_DELAY_INITIAL;
+    let mut failed_cycles: u32 = 0;
+
+    loop {
+        let paused = state.lock().unwrap().paused;
+        if paused {
+            println!("[Rust] Overlay paused, connection loop idle");
+            wait_for_paused(&state, false).await;
+            println!("[Rust] Overlay resumed, reconnecting");
+            reconnect_delay = RECONNECT_DELAY_INITIAL;
+        }
+
+        // Pausing drops the connection cycle, including any request in flight
+        let (received_data, retry_now) = tokio::select! {
+            received_data = connect_and_stream(&client, &app_handle, &state) => (received_data, false),
+            _ = wait_for_paused(&state, true) => {
+                set_connected(&app_handle, &state, false);
+                continue;
+            }
+            // An injected fault drops it like a server that went away
+            _ = wait_for_connection_fault(&state) => (false, false),
+            // Not the client's fault, so it doesn't count as a failed cycle
+            _ = wait_for_connection_change(&state) => (true, true),
+        };
+        set_connected(&app_handle, &state, false);
+
+        if received_data {
+            reconnect_delay = RECONNECT_DELAY_INITIAL;
+            failed_cycles = 0;
+        } else {
+            failed_cycles += 1;
+            if failed_cycles == FAILED_CYCLES_BEFORE_DISCONNECTED {
+                report_disconnected(&app_handle, &state, failed_cycles);
+            }
+        }
+        println!("[Rust] Connection lost, reconnecting in {:?}", reconnect_delay);
+        {
+            let mut state_guard = state.lock().unwrap();
+            state_guard.reconnects += 1;
+            state_guard.reconnect_attempt = failed_cycles;
+            // A retry requested before this wait (e.g. while still connected) doesn't cut it short
+            state_guard.reconnect_requested = retry_now;
+        }
+        tokio::select! {
+            _ = reconnect_countdown(&app_handle, failed_cycles, reconnect_delay) => {
+                if !received_data {
+                    reconnect_delay = (reconnect_delay * 2).min(RECONNECT_DELAY_MAX);
+                }
+            }
+            _ = wait_for_reconnect_request(&state) => {
+                println!("[Rust] Reconnecting now");
+                emit_reconnecting(&app_handle, failed_cycles, reconnect_delay, Duration::ZERO);
+                reconnect_delay = RECONNECT_DELAY_INITIAL;
+                failed_cycles = 0;
+                state.lock().unwrap().reconnect_attempt = 0;
+            }
+        }
+    }
+}
+
+// Tells the frontend the client seems to be down, so it can offer a manual retry
+fn report_disconnected(app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>, failed_attempts: u32) {
+    let tried_ports = app_handle.state::<settings::SharedSettings>().lock().unwrap().candidate_ports();
+    let (last_error, unexpected_response) = {
+        let mut state_guard = state.lock().unwrap();
+        state_guard.status = "Disconnected".to_string();
+        (state_guard.last_error.clone(), state_guard.unexpected_response.clone())
+    };
+    eprintln!("[Rust] Client unreachable after {} attempts (ports {:?})", failed_attempts, tried_ports);
+    app_handle
+        .emit_all("connection-failed", ConnectionFailedEvent { tried_ports, last_error, unexpected_response, failed_attempts })
+        .unwrap_or_else(|e| eprintln!("Failed to emit connection-failed: {}", e));
+}
+
+// Runs one connection cycle, returns true if any status data was received
+async fn connect_and_stream(client: &reqwest::Client, app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>) -> bool {
+    let working_port = find_working_port(client, app_handle, state).await;
+
+    // Always default to a client port, never the server port
+    let forced_port = state.lock().unwrap().forced_port;
+    let (current_port, base_url) = working_port.clone().unwrap_or_else(|| {
+        let shared_settings = app_handle.state::<settings::SharedSettings>();
+        let settings_guard = shared_settings.lock().unwrap();
+        let fallback_port = forced_port.unwrap_or_else(|| settings_guard.candidate_ports()[0]);
+        let fallback_url = settings_guard.client_base_urls(fallback_port).remove(0);
+        println!("[Rust] No CLIENT connection found, using fallback {}", fallback_url);
+        (fallback_port, fallback_url)
+    });
+
+    // Show connection status in UI
+    {
+        let mut state_guard = state.lock().unwrap();
+        if working_port.is_some() {
+            state_guard.status = format!("Connected to CLIENT port {}", current_port);
+        } else if state_guard.status != "Disconnected" {
+            // "Disconnected" stays until the client is found again
+            state_guard.status = "Waiting for client...".to_string();
+        }
+    }
+    set_connected(app_handle, state, working_port.is_some());
+    if working_port.is_some() {
+        sync_pending_client_settings(client, &base_url, app_handle, state).await;
+    }
+
+    let server_info = if working_port.is_some() {
+        match fetch_server_info(client, &base_url).await {
+            Ok(info) => {
+                println!("[Rust] Client info: {:?}", info);
+                Some(info)
+            }
+            Err(e) => {
+                println!("[Rust] No client info available ({}), detecting transports", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let sse_supported = server_info.as_ref().is_none_or(|info| info.supports_transport("sse"));
+    state.lock().unwrap().server_info = server_info;
+
+    let (transport, stream_path) = {
+        let shared_settings = app_handle.state::<settings::SharedSettings>();
+        let settings_guard = shared_settings.lock().unwrap();
+        (settings_guard.transport.clone(), settings_guard.stream_path.clone())
+    };
+    // A forced transport is used even if it fails, so its errors show up instead of a fallback
+    let forced_sse = match transport.as_str() {
+        "polling" => {
+            println!("[Rust] Transport set to polling, skipping SSE");
+            return handle_polling(client, &base_url, current_port, app_handle, state).await;
+        }
+        "websocket" => {
+            return transport_unavailable(state, "The overlay has no websocket transport".to_string(), &transport);
+        }
+        "sse" => true,
+        _ => false,
+    };
+
+    if !sse_supported {
+        if forced_sse {
+            return transport_unavailable(state, "Client doesn't advertise SSE".to_string(), &transport);
+        }
+        println!("[Rust] Client doesn't advertise SSE, using polling");
+        return handle_polling(client, &base_url, current_port, app_handle, state).await;
+    }
+
+    // Try SSE first, fallback to polling if not available
+    let sse_url = format!("{}{}", base_url, stream_path);
+
+    println!("[Rust] Attempting to connect to SSE stream: {}", sse_url);
+
+    // Try to establish SSE connection (timeout only covers getting the response headers)
+    let last_event_id = state.lock().unwrap().sse_last_event_id.clone();
+    let request = sse::request(client, &sse_url, last_event_id.as_deref());
+    let failure = match tokio::time::timeout(HTTP_REQUEST_TIMEOUT, request.send()).await {
+        Ok(Ok(response)) => {
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            if response.status().is_success() && sse::is_event_stream(content_type.as_deref()) {
+                println!("[Rust] Successfully connected to SSE stream");
+                return handle_sse_stream(response, app_handle, state).await;
+            } else if response.status().is_success() {
+                format!("SSE endpoint answered with {:?} instead of an event stream", content_type)
+            } else {
+                format!("SSE not available (status: {})", response.status())
+            }
+        }
+        Ok(Err(e)) => format!("Failed to connect to SSE: {}", describe_request_error(&e)),
+        Err(_) => "SSE connection timed out".to_string(),
+    };
+    if forced_sse {
+        return transport_unavailable(state, failure, &transport);
+    }
+    println!("[Rust] {}, falling back to polling", failure);
+    handle_polling(client, &base_url, current_port, app_handle, state).await
+}
+
+// Ends a connection cycle whose forced transport can't be used; returns false like a failed cycle
+fn transport_unavailable(state: &Arc<Mutex<OverlayState>>, reason: String, transport: &str) -> bool {
+    let error = format!("{} (transport is set to \"{}\", not falling back)", reason, transport);
+    eprintln!("[Rust] {}", error);
+    record_error(state, error);
+    false
+}
+
+// Processes SSE messages until the stream ends, returns true if any data was received
+async fn handle_sse_stream(response: reqwest::Response, app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>) -> bool {
+    let mut stream = response.bytes_stream();
+    let mut parser = sse::SseParser::default();
+    let mut received_data = false;
+    state.lock().unwrap().transport = Some("sse");
+
+    loop {
+        let chunk = match tokio::time::timeout(SSE_IDLE_TIMEOUT, stream.next()).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(_) => {
+                eprintln!("[Rust] No SSE data for {:?}, reconnecting", SSE_IDLE_TIMEOUT);
+                break;
+            }
+        };
+        match chunk {
+            Ok(bytes) => {
+                raw_log::received("sse", &bytes);
+                for event in parser.push(&bytes) {
+                    state.lock().unwrap().sse_last_event_id = event.id.clone();
+                    match serde_json::from_str::<serde_json::Value>(&event.data) {
+                        Ok(data) => {
+                            println!("[Rust] Received SSE {} (id {:?}): {}", event.event, event.id, text_for_log(&data.to_string()));
+                            received_data = true;
+                            process_client_data(data, app_handle, state);
+                        }
+                        Err(e) => {
+                            eprintln!("[Rust] Failed to parse SSE JSON: {}", e);
+                            eprintln!("[Rust] Raw JSON: {}", event.data);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("[Rust] SSE stream error: {}", e);
+                break;
+            }
+        }
+    }
+    println!("[Rust] SSE stream ended, attempting to reconnect...");
+    received_data
+}
+
+// Ticks at a fixed cadence however long each request takes; after a request slower than the
+// interval, the missed ticks are skipped instead of fired in a burst
+fn poll_ticks(interval: Duration) -> tokio::time::Interval {
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    ticks
+}
+
+// Polls until the client stops responding, returns true if any data was received
+async fn handle_polling(client: &reqwest::Client, base_url: &str, current_port: u16, app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>) -> bool {
+    println!("[Rust] Using polling mode on CLIENT at {}", base_url);
+    let mut received_data = false;
+    state.lock().unwrap().transport = Some("polling");
+    let (status_path, mut poll_rate) = {
+        let settings = app_handle.state::<settings::SharedSettings>();
+        let settings = settings.lock().unwrap();
+        (settings.status_path.clone(), poll_rate::PollRate::new(&settings.polling))
+    };
+    let poll_url = format!("{}{}", base_url, status_path);
+
+    let mut ticks = poll_ticks(poll_rate.current());
+    loop {
+        ticks.tick().await;
+
+        let request_started = Instant::now();
+        match client.get(&poll_url).timeout(HTTP_REQUEST_TIMEOUT).send().await {
+            Ok(response) => {
+                if response.status().is_success() {
+                    let body = match response.bytes().await {
+                        Ok(body) => body,
+                        Err(e) => {
+                            eprintln!("[Rust] Failed to read status response: {}", describe_request_error(&e));
+                            continue;
+                        }
+                    };
+                    state.lock().unwrap().poll_latency.observe(request_started.elapsed());
+                    raw_log::received("poll", &body);
+                    match serde_json::from_slice::<serde_json::Value>(&body) {
+                        Ok(data) => {
+                            // Update connection status
+                            {
+                                let mut state_guard = state.lock().unwrap();
+                                state_guard.status = format!("Connected to CLIENT port {}", current_port);
+                            }
+                            set_connected(app_handle, state, true);
+                            received_data = true;
+                            process_client_data(data, app_handle, state);
+                            let active = {
+                                let state_guard = state.lock().unwrap();
+                                state_guard.is_listening || state_guard.is_speaking || state_guard.wake_word_detected
+                            };
+                            let interval = poll_rate.next(active, Instant::now());
+                            if interval != ticks.period() {
+                                ticks = poll_ticks(interval);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("[Rust] Failed to parse JSON response: {}", e);
+                        }
+                    }
+                } else if matches!(response.status().as_u16(), 401 | 403) {
+                    // Retrying every second won't help; end the cycle so the failure is reported
+                    let error = format!("{} requires authentication (status {})", poll_url, response.status());
+                    eprintln!("[Rust] {}", error);
+                    record_error(state, error);
+                    return received_data;
+                } else {
+                    eprintln!("[Rust] CLIENT status endpoint returned error: {}", response.status());
+                }
+            }
+            Err(e) => {
+                let error = format!("Failed to connect to CLIENT port {}: {}", current_port, describe_request_error(&e));
+                println!("[Rust] {}", error);
+                record_error(state, error);
+
+                // Update UI to show waiting for client
+                {
+                    let mut state_guard = state.lock().unwrap();
+                    state_guard.status = "Waiting for client to start...".to_string();
+                    state_guard.text = "Start the Gaja client first".to_string();
+                }
+                // The connection loop rescans the ports and picks the transport again
+                return received_data;
+            }
+        }
+    }
+}
+
+// A payload from the client's stream or status endpoint, recorded for record_session
+fn process_client_data(data: serde_json::Value, app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>) {
+    {
+        let mut state_guard = state.lock().unwrap();
+        if let Some(recorder) = state_guard.session_recording.as_mut() {
+            recorder.record(&data, Instant::now());
+        }
+        if state_guard.replaying_session {
+            return;
+        }
+    }
+    process_status_data(data, app_handle.clone(), state.clone());
+}
+
+fn process_status_data(data: serde_json::Value, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
+    apply_status_data(data, app_handle, state, true);
+}
+
+// Payloads held for the wake word acknowledgement or queued as notifications come back through
+// here with `received` false, so they are only counted and recorded in the history once
+fn apply_status_data(data: serde_json::Value, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>, received: bool) {
+    println!("[Rust] Processing status data: {}", text_for_log(&data.to_string()));
+    let payload = match status::StatusPayload::parse(&data) {
+        Ok(payload) => payload,
+        Err(e) => {
+            eprintln!("[Rust] {}, ignoring it", e);
+            return;
+        }
+    };
+    if received {
+        state.lock().unwrap().status_updates += 1;
+    }
+    let window = match overlay_window(&app_handle) {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("[Rust] {}, dropping status update", e);
+            return;
+        }
+    };
+
+    // Looked up before taking the state lock: monitor queries wait for the main thread,
+    // which may itself be waiting for the lock in a window event handler
+    let follow_active_monitor = app_handle.state::<settings::SharedSettings>().lock().unwrap().overlay.follow_active_monitor;
+    let cursor_monitor = if follow_active_monitor && !state.lock().unwrap().visible {
+        placement::monitor_at_cursor(&window)
+    } else {
+        None
+    };
+
+    let mut state_guard = state.lock().unwrap();
+    if state_guard.paused {
+        return;
+    }
+    state_guard.last_payload = Some(data.clone());
+    if state_guard.dismissed_payload.is_some() {
+        if state_guard.dismissed_payload.as_ref() == Some(&data) {
+            return;
+        }
+        state_guard.dismissed_payload = None;
+    }
+
+    // Tuning scores only feed the settings window's meter
+    if let Some(confidence) = payload.wake_confidence {
+        let saved = app_handle.state::<settings::SharedSettings>().lock().unwrap().voice.sensitivity;
+        let sensitivity = state_guard.tuning.sensitivity(saved);
+        drop(state_guard);
+        let event = WakeConfidence { confidence, sensitivity };
+        raw_log::emitted("wake-confidence", &event);
+        app_handle
+            .emit_all("wake-confidence", event)
+            .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit wake-confidence: {}", e));
+        return;
+    }
+
+    // Action payloads run the action and don't change what the overlay shows
+    if let Some(action) = actions::Action::from_payload(&payload) {
+        if state_guard.last_action.as_ref() == Some(&data) {
+            return;
+        }
+        state_guard.last_action = Some(data);
+        drop(state_guard);
+        match action {
+            Ok(action) => run_action(&app_handle, action),
+            Err(e) => eprintln!("[Rust] Ignoring action: {}", e),
+        }
+        return;
+    }
+
+    let fields = status::StatusFields::from_payload(&payload);
+    let status = payload.status.clone().unwrap_or_else(|| "Unknown".to_string());
+    let current_text = fields.text.to_string();
+    let is_listening = fields.is_listening;
+    let is_speaking = fields.is_speaking;
+    let wake_word_detected = fields.wake_word_detected;
+    // Servers that don't stream responses never send this, so text is final by default
+    let partial = payload.partial.unwrap_or(false);
+
+    let (language, notification_min_duration, ack_duration, min_chars, show_live_when_disabled, live_during_snooze, dnd_active) = {
+        let settings = app_handle.state::<settings::SharedSettings>();
+        let settings = settings.lock().unwrap();
+        (
+            settings.voice.language.clone(),
+            Duration::from_millis(settings.overlay.notification_min_duration_ms),
+            Duration::from_millis(settings.overlay.ack_duration_ms),
+            status::MinDisplayChars {
+                response: settings.overlay.min_display_chars,
+                raw_text: settings.overlay.min_raw_text_chars,
+            },
+            settings.overlay.show_live_when_disabled,
+            settings.overlay.live_during_snooze,
+            dnd_active_now(&settings),
+        )
+    };
+
+    // Visibility follows the structured state (legacy flags/text are only a fallback)
+    let decision = status::should_show(&fields, min_chars);
+    let display_state = decision.state;
+    let should_be_visible = decision.visible;
+
+    if received {
+        if state_guard.status_history.len() == STATUS_HISTORY_LEN {
+            state_guard.status_history.pop_front();
+        }
+        let received_at_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        state_guard.status_history.push_back(StatusHistoryEntry {
+            received_at_ms,
+            state: display_state,
+            visible: should_be_visible,
+            payload: data.clone(),
+        });
+    }
+
+    // Keep the wake word acknowledgement up for ack_duration, even if the client goes idle sooner
+    if let Some(until) = state_guard.wake_ack_until.filter(|until| *until > Instant::now()) {
+        if !should_be_visible {
+            if state_guard.held_after_ack.replace(data).is_none() {
+                schedule_ack_release(app_handle.clone(), state.clone(), until - Instant::now());
+            }
+            return;
+        }
+    }
+    // Anything held is older than this update
+    state_guard.held_after_ack = None;
+    if wake_word_detected && !state_guard.wake_word_detected && !ack_duration.is_zero() {
+        state_guard.wake_ack_until = Some(Instant::now() + ack_duration);
+    }
+
+    // Don't let a new notification replace one that hasn't been on screen long enough
+    if display_state == Status::Notification && state_guard.text != current_text {
+        let holding = state_guard.state == Status::Notification
+            && state_guard.notification_shown_at.is_some_and(|t| t.elapsed() < notification_min_duration);
+        if holding {
+            let already_queued = state_guard.notification_queue.iter()
+                .any(|queued| status::StatusPayload::parse(queued).is_ok_and(|queued| queued.display_text() == current_text));
+            if !already_queued {
+                state_guard.notification_queue.push_back(data);
+                println!("[Rust] Notification queued ({} waiting)", state_guard.notification_queue.len());
+            }
+            return;
+        }
+    }
+
+    let mut changed = false;
+    if state_guard.text != current_text ||
+        state_guard.partial != partial ||
+        state_guard.state != display_state ||
+        state_guard.is_listening != is_listening ||
+        state_guard.is_speaking != is_speaking ||
+        state_guard.wake_word_detected != wake_word_detected
+    {
+        changed = true;
+    }
+
+    if changed {
+        println!("[Rust] Status update: state={:?}, listening={}, speaking={}, wake_word={}, text='{}', partial={}, visible={}",
+                display_state, is_listening, is_speaking, wake_word_detected, text_for_log(&current_text), partial, should_be_visible);
+
+        state_guard.status = status.clone();
+        state_guard.state = display_state;
+        state_guard.text = current_text.clone();
+        state_guard.partial = partial;
+        state_guard.is_listening = is_listening;
+        state_guard.is_speaking = is_speaking;
+        state_guard.wake_word_detected = wake_word_detected;
+
+        // The window stays up when content ends and is hidden by the auto-hide below
+        let may_show = status::may_show(
+            decision,
+            status::Suppression {
+                user_disabled: state_guard.user_disabled,
+                show_live_when_disabled,
+                dnd_active,
+                snoozed: state_guard.snooze_active(),
+                live_during_snooze,
+            },
+        );
+        if may_show && !state_guard.visible {
+            if let Some(monitor) = &cursor_monitor {
+                follow_monitor(&window, &mut state_guard, monitor);
+            }
+            set_overlay_visible(&window, &mut state_guard, true, "content")
+                .unwrap_or_else(|e| eprintln!("Failed to show window: {}", e));
+        }
+        // Emit status update to frontend
+        let payload = current_status_update(&state_guard, &language);
+        emit_status_update(&window, &state, &mut state_guard, payload);
+        state_guard.last_activity_time = Instant::now();
+
+        if display_state == Status::Notification {
+            state_guard.notification_shown_at = Some(Instant::now());
+            schedule_notification_advance(app_handle.clone(), state.clone(), notification_min_duration);
+        }
+    }    // Auto-hide logic - only hide after longer period and when truly inactive
+    if state_guard.visible && state_guard.last_activity_time.elapsed() > Duration::from_secs(30)
+        && !should_be_visible {
+        println!("[Rust] Auto-hiding window due to prolonged inactivity and no relevant status.");
+        set_overlay_visible(&window, &mut state_guard, false, "auto-hide")
+            .unwrap_or_else(|e| eprintln!("Failed to hide window: {}", e));
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct WakeConfidence {
+    confidence: f64,
+    // Sensitivity in effect (the preview while tuning), for comparison
+    sensitivity: f64,
+}
+
+// Turns the client's wake word tuning mode on or off. While it is on, the client reports
+// detection scores as `wake_confidence` payloads and uses `sensitivity` instead of its saved value.
+async fn send_tuning(client: &reqwest::Client, state: &SharedState, enabled: bool, sensitivity: Option<f64>) -> Result<(), String> {
+    let base_url = {
+        let state_guard = state.lock().unwrap();
+        state_guard.last_good_base_url.clone().filter(|_| state_guard.connected)
+    }
+    .ok_or("Not connected to the client")?;
+    let response = client
+        .post(format!("{}/api/wake_word/tuning", base_url))
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .json(&serde_json::json!({ "enabled": enabled, "sensitivity": sensitivity }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach client: {}", describe_request_error(&e)))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err("The client doesn't support sensitivity tuning (no /api/wake_word/tuning endpoint)".to_string());
+    }
+    if !response.status().is_success() {
+        return Err(format!("Client returned status {} for /api/wake_word/tuning", response.status()));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn start_sensitivity_tuning(
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    let saved = settings.lock().unwrap().voice.sensitivity;
+    let sensitivity = state.lock().unwrap().tuning.sensitivity(saved);
+    send_tuning(&client, &state, true, Some(sensitivity)).await?;
+    state.lock().unwrap().tuning.start();
+    println!("[Rust] Sensitivity tuning started at {}", sensitivity);
+    Ok(())
+}
+
+// Tries a sensitivity on the client without saving it; saving the settings keeps it
+#[tauri::command]
+async fn set_sensitivity_preview(
+    value: f64,
+    client: tauri::State<'_, reqwest::Client>,
+    settings: tauri::State<'_, settings::SharedSettings>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    let mut candidate = settings.lock().unwrap().clone();
+    candidate.voice.sensitivity = value;
+    candidate.validate()?;
+    send_tuning(&client, &state, true, Some(value)).await?;
+    state.lock().unwrap().tuning.set_preview(value);
+    println!("[Rust] Previewing wake word sensitivity {}", value);
+    Ok(())
+}
+
+// The client goes back to its saved sensitivity
+#[tauri::command]
+async fn stop_sensitivity_tuning(
+    client: tauri::State<'_, reqwest::Client>,
+    state: tauri::State<'_, SharedState>
+) -> Result<(), String> {
+    state.lock().unwrap().tuning.stop();
+    send_tuning(&client, &state, false, None).await?;
+    println!("[Rust] Sensitivity tuning stopped");
+    Ok(())
 }
 
-impl OverlayState {
-    fn new() -> Self {
-        OverlayState {
-            visible: false,
-            status: "Offline".to_string(),
-            text: "".to_string(),
-            is_listening: false,
-            is_speaking: false,
-            wake_word_detected: false,
-            last_activity_time: Instant::now(),
+// Ends tuning the settings window started, if it is still on, when the window is closed
+async fn stop_tuning_for_closed_window(app_handle: AppHandle) {
+    let state = app_handle.state::<SharedState>().inner().clone();
+    if !state.lock().unwrap().tuning.stop() {
+        return;
+    }
+    app_handle
+        .emit_to("settings", "tuning-stopped", ())
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit tuning-stopped: {}", e));
+    let client = app_handle.state::<reqwest::Client>().inner().clone();
+    match send_tuning(&client, &state, false, None).await {
+        Ok(()) => println!("[Rust] Sensitivity tuning stopped, settings window closed"),
+        Err(e) => eprintln!("[Rust] Failed to stop sensitivity tuning: {}", e),
+    }
+}
+
+fn run_action(app_handle: &AppHandle, action: actions::Action) {
+    match action {
+        actions::Action::OpenUrl(url) => {
+            println!("[Rust] Opening {}", url);
+            tauri::api::shell::open(&app_handle.shell_scope(), &url, None)
+                .unwrap_or_else(|e| eprintln!("[Rust] Failed to open {}: {}", url, e));
+        }
+        actions::Action::Notify { title, body } => {
+            let (enabled, dnd_active) = {
+                let settings = app_handle.state::<settings::SharedSettings>();
+                let settings = settings.lock().unwrap();
+                (settings.overlay.notifications_enabled, dnd_active_now(&settings))
+            };
+            if !enabled {
+                println!("[Rust] Desktop notifications are disabled, dropping '{}'", title);
+                return;
+            }
+            if dnd_active {
+                println!("[Rust] Do not disturb is active, dropping '{}'", title);
+                return;
+            }
+            if app_handle.state::<SharedState>().lock().unwrap().snooze_active() {
+                println!("[Rust] Overlay is snoozed, dropping '{}'", title);
+                return;
+            }
+            // Shown natively, so it doesn't depend on the overlay page being loaded
+            tauri::api::notification::Notification::new(&app_handle.config().tauri.bundle.identifier)
+                .title(&title)
+                .body(body)
+                .notify(app_handle)
+                .unwrap_or_else(|e| eprintln!("[Rust] Failed to show notification '{}': {}", title, e));
         }
     }
 }
 
-type SharedState = Arc<Mutex<OverlayState>>;
+// How often the briefing scheduler re-reads its settings and the clock, so changes to either
+// are picked up while waiting for the next briefing
+const BRIEFING_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+// The client may have to fetch weather and the like before it can answer
+const BRIEFING_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
-#[tauri::command]
-async fn show_overlay(window: Window, state: tauri::State<'_, SharedState>) -> Result<(), String> {
-    window.show().map_err(|e| e.to_string())?;
-    {
-        let mut overlay_state = state.lock().unwrap();
-        overlay_state.visible = true;
-    }
-    Ok(())
+// Same lookup the server's onboarding uses; the free tier is http only
+const GEOLOCATION_URL: &str = "http://ip-api.com/json/?fields=status,message,city,countryCode";
+
+async fn lookup_location(client: &reqwest::Client) -> Result<String, String> {
+    let response: serde_json::Value = client
+        .get(GEOLOCATION_URL)
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Location lookup failed: {}", describe_request_error(&e)))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid location lookup response: {}", e))?;
+    briefing::location_from_geolocation(&response)
 }
 
+// Approximate location from the public IP, cached for the rest of the run. Doesn't change the
+// settings; the settings window offers the result for daily_briefing.location.
 #[tauri::command]
-async fn hide_overlay(window: Window, state: tauri::State<'_, SharedState>) -> Result<(), String> {
-    window.hide().map_err(|e| e.to_string())?;
-    {
-        let mut overlay_state = state.lock().unwrap();
-        overlay_state.visible = false;
+async fn detect_location(client: tauri::State<'_, reqwest::Client>, state: tauri::State<'_, SharedState>) -> Result<String, String> {
+    let cached = state.lock().unwrap().detected_location.clone();
+    if let Some(location) = cached {
+        return Ok(location);
     }
-    Ok(())
+    let location = lookup_location(&client).await?;
+    println!("[Rust] Detected location {}", location);
+    state.lock().unwrap().detected_location = Some(location.clone());
+    Ok(location)
 }
 
-#[tauri::command]
-async fn update_status(
-    window: Window,
-    status: String,
+#[derive(Debug, Deserialize)]
+struct BriefingResponse {
     text: String,
-    is_listening: bool,
-    is_speaking: bool,
-    wake_word_detected: bool,
-    state: tauri::State<'_, SharedState>
-) -> Result<(), String> {
-    {
-        let mut overlay_state = state.lock().unwrap();
-        overlay_state.status = status.clone();
-        overlay_state.text = text.clone();
-        overlay_state.is_listening = is_listening;
-        overlay_state.is_speaking = is_speaking;
-        overlay_state.wake_word_detected = wake_word_detected;
+}
+
+async fn fetch_briefing(client: &reqwest::Client, base_url: &str, location: &str) -> Result<String, String> {
+    let response = client
+        .post(format!("{}/api/briefing", base_url))
+        .timeout(BRIEFING_REQUEST_TIMEOUT)
+        .json(&serde_json::json!({ "location": location }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach client: {}", describe_request_error(&e)))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err("The client doesn't provide briefings (no /api/briefing endpoint)".to_string());
     }
+    if !response.status().is_success() {
+        return Err(format!("Client returned status {} for /api/briefing", response.status()));
+    }
+    let briefing: BriefingResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid briefing from client: {}", e))?;
+    Ok(briefing.text)
+}
 
-    window.emit("status-update", serde_json::json!({
-        "status": status,
-        "text": text,
-        "is_listening": is_listening,
-        "is_speaking": is_speaking,
-        "wake_word_detected": wake_word_detected
-    })).map_err(|e| e.to_string())?;
+// Requests a briefing from the client and shows it as a notification, returns the briefing text.
+// With `force_show` the overlay is shown even if the user turned it off.
+async fn deliver_briefing(client: &reqwest::Client, app_handle: &AppHandle, state: &SharedState, force_show: bool) -> Result<String, String> {
+    let base_url = {
+        let state_guard = state.lock().unwrap();
+        if state_guard.paused {
+            return Err("Overlay is paused".to_string());
+        }
+        state_guard.last_good_base_url.clone().filter(|_| state_guard.connected)
+    }
+    .ok_or("Not connected to the client")?;
+    let mut location = app_handle.state::<settings::SharedSettings>().lock().unwrap().daily_briefing.location.clone();
+    if location.trim().is_empty() {
+        // Empty lets the client use its own default
+        location = state.lock().unwrap().detected_location.clone().unwrap_or_default();
+    }
 
-    Ok(())
+    let text = fetch_briefing(client, &base_url, &location).await?;
+    println!("[Rust] Daily briefing received ({} chars)", text.chars().count());
+    let payload = serde_json::json!({ "state": "notification", "status": "Briefing", "text": text });
+    process_status_data(payload, app_handle.clone(), state.clone());
+    if force_show {
+        let window = overlay_window(app_handle)?;
+        set_overlay_visible(&window, &mut state.lock().unwrap(), true, "command")?;
+    }
+    Ok(text)
 }
 
+// Reads the briefing now, using the same request as the daily schedule
 #[tauri::command]
-fn get_state(state: tauri::State<Arc<Mutex<OverlayState>>>) -> Result<OverlayState, String> { // Ensure State is tauri::State
-    Ok(state.inner().lock().unwrap().clone())
+async fn trigger_briefing(
+    client: tauri::State<'_, reqwest::Client>,
+    app_handle: AppHandle,
+    state: tauri::State<'_, SharedState>
+) -> Result<String, String> {
+    deliver_briefing(&client, &app_handle, &state, true).await
 }
 
-async fn poll_assistant_status(app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
-    let client = reqwest::Client::new();
-    // ONLY try client ports - NEVER connect to main server (8001)
-    let ports = vec!["5001", "5000"];
-    let mut working_port = None;
-
-    // First, find which port is working
-    for port in &ports {
-        let test_url = format!("http://localhost:{}/api/status", port);
-        println!("[Rust] Testing connection to CLIENT port {}", port);
-        match client.get(&test_url).timeout(std::time::Duration::from_secs(2)).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    working_port = Some(port.to_string());
-                    println!("[Rust] Found working CLIENT port: {}", port);
-                    break;
-                } else {
-                    println!("[Rust] Client port {} returned status: {}", port, response.status());
+// Fires the daily briefing at DailyBriefingSettings.briefing_time (local time) every day,
+// and once after the first connection if startup_briefing is set
+async fn run_briefing_scheduler(client: reqwest::Client, app_handle: AppHandle, state: SharedState) {
+    let (startup, detect) = {
+        let settings = app_handle.state::<settings::SharedSettings>();
+        let briefing_settings = &settings.lock().unwrap().daily_briefing;
+        (
+            briefing_settings.enabled && briefing_settings.startup_briefing,
+            briefing_settings.enabled && briefing_settings.detect_location && briefing_settings.location.trim().is_empty(),
+        )
+    };
+    if detect {
+        // In the background so a slow lookup doesn't hold up anything else
+        let (client, state) = (client.clone(), state.clone());
+        tauri::async_runtime::spawn(async move {
+            match lookup_location(&client).await {
+                Ok(location) => {
+                    println!("[Rust] Detected location {} for the daily briefing", location);
+                    state.lock().unwrap().detected_location = Some(location);
                 }
+                Err(e) => eprintln!("[Rust] {}, the client's default location will be used", e),
             }
-            Err(e) => {
-                println!("[Rust] Client port {} connection failed: {}", port, e);
+        });
+    }
+    if startup {
+        let (client, app_handle, state) = (client.clone(), app_handle.clone(), state.clone());
+        tauri::async_runtime::spawn(async move {
+            while !state.lock().unwrap().connected {
+                sleep(FLAG_CHECK_INTERVAL).await;
             }
-        }
+            println!("[Rust] Requesting startup briefing");
+            if let Err(e) = deliver_briefing(&client, &app_handle, &state, false).await {
+                eprintln!("[Rust] Startup briefing failed: {}", e);
+            }
+        });
     }
 
-    let current_port = working_port.clone().unwrap_or_else(|| {
-        println!("[Rust] No CLIENT connection found, using fallback port 5001");
-        std::env::var("GAJA_PORT").unwrap_or_else(|_| {
-            "5001".to_string() // Always default to client port, never server port
-        })
-    });
+    // Next fire time and the briefing_time setting it was computed from
+    let mut next: Option<(chrono::DateTime<chrono::Local>, String)> = None;
+    loop {
+        state.lock().unwrap().next_briefing_at = next.as_ref().map(|(at, _)| *at);
+        let (enabled, briefing_time) = {
+            let settings = app_handle.state::<settings::SharedSettings>();
+            let briefing_settings = &settings.lock().unwrap().daily_briefing;
+            (briefing_settings.enabled, briefing_settings.briefing_time.clone())
+        };
+        if !enabled {
+            next = None;
+            state.lock().unwrap().next_briefing_at = None;
+            sleep(BRIEFING_CHECK_INTERVAL).await;
+            continue;
+        }
 
-    // Show connection status in UI
-    {
-        let mut state_guard = state.lock().unwrap();
-        if working_port.is_some() {
-            state_guard.status = format!("Connected to CLIENT port {}", current_port);
-        } else {
-            state_guard.status = "Waiting for client...".to_string();
+        let now = chrono::Local::now();
+        let fire_at = match &next {
+            Some((at, computed_from)) if *computed_from == briefing_time => *at,
+            _ => match briefing::parse_briefing_time(&briefing_time) {
+                Ok(time) => {
+                    let at = briefing::next_briefing(&now, time);
+                    println!("[Rust] Next daily briefing at {}", at);
+                    next = Some((at, briefing_time));
+                    continue;
+                }
+                Err(e) => {
+                    // save_settings rejects this, but the file may have been edited by hand
+                    eprintln!("[Rust] Daily briefing disabled, daily_briefing.briefing_time: {}", e);
+                    next = None;
+                    state.lock().unwrap().next_briefing_at = None;
+                    sleep(BRIEFING_CHECK_INTERVAL).await;
+                    continue;
+                }
+            },
+        };
+
+        match briefing::briefing_due(&now, &fire_at) {
+            briefing::BriefingDue::Wait(remaining) => {
+                sleep(remaining.to_std().unwrap_or_default().min(BRIEFING_CHECK_INTERVAL)).await;
+            }
+            briefing::BriefingDue::Fire => {
+                // Recomputed from a time past this one, so the next briefing is tomorrow's
+                next = None;
+                println!("[Rust] Requesting daily briefing");
+                if let Err(e) = deliver_briefing(&client, &app_handle, &state, false).await {
+                    eprintln!("[Rust] Daily briefing failed: {}", e);
+                }
+            }
+            briefing::BriefingDue::Missed => {
+                eprintln!("[Rust] Missed the daily briefing at {} (now {}), skipping it", fire_at, now);
+                next = None;
+            }
+            briefing::BriefingDue::Reschedule => {
+                println!("[Rust] Clock was set back, rescheduling the daily briefing");
+                next = None;
+            }
         }
     }
+}
 
-    // Try SSE first, fallback to polling if not available
-    let sse_url = format!("http://localhost:{}/status/stream", current_port);
-
-    println!("[Rust] Attempting to connect to SSE stream: {}", sse_url);
+// Applies the update held back while the wake word acknowledgement was on screen
+fn schedule_ack_release(app_handle: AppHandle, state: SharedState, delay: Duration) {
+    tauri::async_runtime::spawn(async move {
+        sleep(delay).await;
+        let held = state.lock().unwrap().held_after_ack.take();
+        if let Some(held) = held {
+            apply_status_data(held, app_handle, state, false);
+        }
+    });
+}
 
-    // Try to establish SSE connection
-    match client.get(&sse_url).timeout(std::time::Duration::from_secs(5)).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                println!("[Rust] Successfully connected to SSE stream");
-                handle_sse_stream(response, app_handle.clone(), state.clone()).await;
+// Shows the next queued notification once the current one has had its minimum duration
+fn schedule_notification_advance(app_handle: AppHandle, state: SharedState, delay: Duration) {
+    tauri::async_runtime::spawn(async move {
+        sleep(delay).await;
+        let next = {
+            let mut state_guard = state.lock().unwrap();
+            if state_guard.notification_queue.is_empty() {
+                return;
+            }
+            if state_guard.state.is_live() {
+                None
             } else {
-                println!("[Rust] SSE not available (status: {}), falling back to polling", response.status());
-                handle_polling(client, current_port, app_handle, state).await;
+                state_guard.notification_queue.pop_front()
             }
+        };
+        match next {
+            Some(next) => apply_status_data(next, app_handle, state, false),
+            // Listening/speaking win; try again once the interaction is over
+            None => schedule_notification_advance(app_handle, state, delay),
         }
-        Err(e) => {
-            println!("[Rust] Failed to connect to SSE: {}, falling back to polling", e);
-            handle_polling(client, current_port, app_handle, state).await;
-        }
+    });
+}
+
+fn build_system_tray() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("toggle_overlay", "Show/Hide overlay"))
+        .add_item(CustomMenuItem::new("clear", "Dismiss message"))
+        .add_item(CustomMenuItem::new("pause", "Pause overlay"))
+        .add_item(CustomMenuItem::new("snooze", format!("Snooze {} min", TRAY_SNOOZE_MINUTES)))
+        .add_item(CustomMenuItem::new("reconnect", "Reconnect now"))
+        .add_item(CustomMenuItem::new("open_settings", "Settings"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("quit", "Quit"));
+    SystemTray::new()
+        .with_menu(menu)
+        .with_tooltip(&tray_tooltip(false, false))
+}
+
+fn tray_tooltip(connected: bool, dnd_active: bool) -> String {
+    let connection = if connected { "connected" } else { "not connected" };
+    if dnd_active {
+        format!("Gaja Overlay - {}, do not disturb", connection)
+    } else {
+        format!("Gaja Overlay - {}", connection)
     }
 }
 
-async fn handle_sse_stream(response: reqwest::Response, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+fn update_tray_tooltip(app_handle: &AppHandle, connected: bool, dnd_active: bool) {
+    app_handle
+        .tray_handle()
+        .set_tooltip(&tray_tooltip(connected, dnd_active))
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to update tray tooltip: {}", e));
+}
 
-    while let Some(chunk) = stream.next().await {
-        match chunk {
-            Ok(bytes) => {
-                let chunk_str = String::from_utf8_lossy(&bytes);
-                buffer.push_str(&chunk_str);
-                  // Process complete SSE messages
-                while let Some(pos) = buffer.find("\n\n") {
-                    let message = buffer[..pos].to_string();
-                    buffer.drain(..pos + 2);
-
-                    if message.starts_with("data: ") {
-                        let json_str = &message[6..]; // Remove "data: " prefix
-                          match serde_json::from_str::<serde_json::Value>(json_str) {
-                            Ok(data) => {
-                                println!("[Rust] Received SSE data: {}", data);
-                                process_status_data(data, app_handle.clone(), state.clone()).await;
-                            }
-                            Err(e) => {
-                                eprintln!("[Rust] Failed to parse SSE JSON: {}", e);
-                                eprintln!("[Rust] Raw JSON: {}", json_str);
-                            }
-                        }
-                    }
+fn handle_system_tray_event(app_handle: &AppHandle, event: SystemTrayEvent) {
+    if let SystemTrayEvent::MenuItemClick { id, .. } = event {
+        match id.as_str() {
+            "toggle_overlay" => {
+                let state = app_handle.state::<SharedState>();
+                if let Err(e) = toggle_overlay(app_handle, &state) {
+                    eprintln!("[Rust] Failed to toggle overlay from tray: {}", e);
                 }
             }
-            Err(e) => {
-                eprintln!("[Rust] SSE stream error: {}", e);
-                break;
+            "clear" => {
+                let state = app_handle.state::<SharedState>();
+                if let Err(e) = request_clear_overlay(app_handle, &state) {
+                    eprintln!("[Rust] Failed to clear overlay from tray: {}", e);
+                }
             }
+            "pause" => {
+                let state = app_handle.state::<SharedState>();
+                let paused = state.lock().unwrap().paused;
+                if let Err(e) = set_paused_state(app_handle, &state, !paused) {
+                    eprintln!("[Rust] Failed to toggle pause from tray: {}", e);
+                }
+            }
+            "snooze" => {
+                let state = app_handle.state::<SharedState>();
+                let minutes = if state.lock().unwrap().snooze_active() { 0 } else { TRAY_SNOOZE_MINUTES };
+                if let Err(e) = snooze(app_handle, &state, minutes) {
+                    eprintln!("[Rust] Failed to snooze from tray: {}", e);
+                }
+            }
+            "reconnect" => {
+                app_handle.state::<SharedState>().lock().unwrap().reconnect_requested = true;
+            }
+            "open_settings" => {
+                if let Err(e) = open_settings_window(app_handle) {
+                    eprintln!("[Rust] {}", e);
+                }
+            }
+            "quit" => {
+                println!("[Rust] Quit requested from tray");
+                // exit() ends the process right away, without a RunEvent::Exit
+                app_handle.state::<settings::SettingsWriter>().flush();
+                notify_overlay_shutdown(app_handle, "quit");
+                app_handle.exit(0);
+            }
+            _ => {}
         }
     }
-      println!("[Rust] SSE stream ended, attempting to reconnect...");
-    // Reconnect after a delay
-    tokio::time::sleep(Duration::from_secs(5)).await;
-    Box::pin(poll_assistant_status(app_handle, state)).await;
 }
 
-async fn handle_polling(client: reqwest::Client, mut current_port: String, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
-    println!("[Rust] Using polling mode on CLIENT port {}", current_port);
+fn dnd_active_now(settings: &settings::Settings) -> bool {
+    dnd::is_active(&settings.do_not_disturb, chrono::Local::now().naive_local())
+}
+
+// How often the do-not-disturb schedule is checked for dnd-changed and the tray tooltip.
+// Status updates check the clock themselves, so they aren't affected by this delay.
+const DND_CHECK_INTERVAL: Duration = Duration::from_secs(15);
 
+// Emits "dnd-changed" (true/false) when a do-not-disturb window starts or ends
+async fn run_dnd_watcher(app_handle: AppHandle, state: SharedState) {
     loop {
-        sleep(Duration::from_millis(1000)).await; // Poll every 1 second
+        let active = dnd_active_now(&app_handle.state::<settings::SharedSettings>().lock().unwrap());
+        let changed = {
+            let mut state_guard = state.lock().unwrap();
+            let changed = state_guard.dnd_active != active;
+            state_guard.dnd_active = active;
+            changed.then_some(state_guard.connected)
+        };
+        if let Some(connected) = changed {
+            println!("[Rust] Do not disturb {}", if active { "started" } else { "ended" });
+            update_tray_tooltip(&app_handle, connected, active);
+            app_handle
+                .emit_all("dnd-changed", active)
+                .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit dnd-changed: {}", e));
+        }
+        sleep(DND_CHECK_INTERVAL).await;
+    }
+}
 
-        let poll_url = format!("http://localhost:{}/api/status", current_port);
-        match client.get(&poll_url).timeout(Duration::from_secs(3)).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<serde_json::Value>().await {
-                        Ok(data) => {
-                            // Update connection status
-                            {
-                                let mut state_guard = state.lock().unwrap();
-                                state_guard.status = format!("Connected to CLIENT port {}", current_port);
-                            }
-                            process_status_data(data, app_handle.clone(), state.clone()).await;
-                        }
-                        Err(e) => {
-                            eprintln!("[Rust] Failed to parse JSON response: {}", e);
-                        }
-                    }
-                } else {
-                    eprintln!("[Rust] CLIENT status endpoint returned error: {}", response.status());
-                }
+// How often run_topmost_reasserter checks the setting while reasserting is off
+const TOPMOST_SETTING_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Re-applies the "top" z-order every overlay.reassert_topmost_ms while the overlay is shown
+async fn run_topmost_reasserter(app_handle: AppHandle, state: SharedState) {
+    loop {
+        let interval_ms = app_handle.state::<settings::SharedSettings>().lock().unwrap().overlay.reassert_topmost_ms;
+        if interval_ms == 0 {
+            sleep(TOPMOST_SETTING_CHECK_INTERVAL).await;
+            continue;
+        }
+        sleep(Duration::from_millis(interval_ms)).await;
+        // Left alone while interactive, like everywhere else the z-order is applied
+        let shown = {
+            let state_guard = state.lock().unwrap();
+            state_guard.visible && !state_guard.interactive
+        };
+        if let (true, Ok(window)) = (shown, overlay_window(&app_handle)) {
+            if current_z_order(&window) == placement::ZOrder::Top {
+                apply_z_order(&window);
             }
-            Err(e) => {
-                println!("[Rust] Failed to connect to CLIENT port {}: {}", current_port, e);
+        }
+    }
+}
 
-                // Update UI to show waiting for client
-                {
-                    let mut state_guard = state.lock().unwrap();
-                    state_guard.status = "Waiting for client to start...".to_string();
-                    state_guard.text = "Start the Gaja client first".to_string();
-                }
+// Values for a metrics scrape, the same counters get_diagnostics reports
+fn metrics_snapshot(state: &SharedState) -> metrics::Snapshot {
+    let state_guard = state.lock().unwrap();
+    metrics::Snapshot {
+        status_updates: state_guard.status_updates,
+        reconnects: state_guard.reconnects,
+        poll_task_restarts: state_guard.poll_task_restarts,
+        connected: state_guard.connected,
+        transport: state_guard.transport,
+        notification_queue_length: state_guard.notification_queue.len(),
+        poll_latency: state_guard.poll_latency.clone(),
+    }
+}
 
-                // Try only client ports if connection fails
-                for test_port in &["5001", "5000"] {
-                    if test_port != &current_port {
-                        let test_url = format!("http://localhost:{}/api/status", test_port);
-                        if let Ok(response) = client.get(&test_url).timeout(Duration::from_secs(2)).send().await {
-                            if response.status().is_success() {
-                                println!("[Rust] Successfully reconnected to CLIENT port {}, switching...", test_port);
-                                current_port = test_port.to_string();
-                                break;
-                            }
-                        }
-                    }
-                }
+// Serves /metrics if Settings.metrics is enabled; changes take effect after a restart
+fn start_metrics_server(settings: &settings::SharedSettings, state: SharedState) {
+    let metrics_settings = settings.lock().unwrap().metrics.clone();
+    if !metrics_settings.enabled {
+        return;
+    }
+    let address = match metrics_settings.bind_address.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            eprintln!("[Rust] Metrics disabled, invalid bind address '{}': {}", metrics_settings.bind_address, e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = metrics::serve(address, metrics_settings.port, move || metrics_snapshot(&state)).await {
+            eprintln!("[Rust] Metrics server on port {} failed: {}", metrics_settings.port, e);
+        }
+    });
+}
 
-                // Wait longer before retrying when no client available
-                sleep(Duration::from_secs(10)).await;
-            }
+// Records connection state and mirrors it in the tray tooltip
+fn set_connected(app_handle: &AppHandle, state: &Arc<Mutex<OverlayState>>, connected: bool) {
+    let (changed, dnd_active) = {
+        let mut state_guard = state.lock().unwrap();
+        let changed = state_guard.connected != connected;
+        state_guard.connected = connected;
+        if connected {
+            state_guard.last_error = None;
+            state_guard.unexpected_response = None;
+            state_guard.reconnect_attempt = 0;
+        } else {
+            state_guard.transport = None;
+        }
+        (changed, state_guard.dnd_active)
+    };
+    if changed {
+        update_tray_tooltip(app_handle, connected, dnd_active);
+        emit_connection_change(app_handle, state);
+        if connected {
+            // The settings window gets no status-update; this ends its reconnect countdown
+            app_handle
+                .emit_to("settings", "connected", ())
+                .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit connected: {}", e));
         }
     }
 }
 
-async fn process_status_data(data: serde_json::Value, app_handle: AppHandle, state: Arc<Mutex<OverlayState>>) {
-    println!("[Rust] Processing status data: {}", data);
+// Sends a status-update with the new connection status; the content itself is unchanged
+fn emit_connection_change(app_handle: &AppHandle, state: &SharedState) {
+    let Ok(window) = overlay_window(app_handle) else {
+        return;
+    };
+    let language = app_handle.state::<settings::SharedSettings>().lock().unwrap().voice.language.clone();
     let mut state_guard = state.lock().unwrap();
-    let window = app_handle.get_window("main").unwrap();
-
-    // Extract data from JSON
-    let status = data.get("status").and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
-    let current_text = data.get("text").and_then(|v| v.as_str()).unwrap_or("").to_string();
-    let is_listening = data.get("is_listening").and_then(|v| v.as_bool()).unwrap_or(false);
-    let is_speaking = data.get("is_speaking").and_then(|v| v.as_bool()).unwrap_or(false);
-    let wake_word_detected = data.get("wake_word_detected").and_then(|v| v.as_bool()).unwrap_or(false);
+    let payload = current_status_update(&state_guard, &language);
+    emit_status_update(&window, state, &mut state_guard, payload);
+}
 
-    // More generous visibility logic - keep overlay visible if there's any activity or recent text
-    let has_activity = wake_word_detected || is_speaking || is_listening;
-    let has_content = !current_text.is_empty();
-    let should_be_visible = has_activity || has_content;
+// Minimum time between follow_active_monitor moves, so an overlay shown repeatedly while the
+// mouse crosses between screens doesn't jump back and forth
+const MONITOR_FOLLOW_DEBOUNCE: Duration = Duration::from_secs(2);
 
-    let mut changed = false;
-    if state_guard.text != current_text ||
-        state_guard.is_listening != is_listening ||
-        state_guard.is_speaking != is_speaking ||
-        state_guard.wake_word_detected != wake_word_detected ||
-        state_guard.visible != should_be_visible
-    {
-        changed = true;
+// Moves the overlay onto `monitor` if it isn't there already. Only sends window changes
+// (no queries), so it's safe to call with the state locked.
+fn follow_monitor(window: &Window, overlay_state: &mut OverlayState, monitor: &tauri::Monitor) {
+    if overlay_state.current_monitor.as_ref() == monitor.name() {
+        return;
+    }
+    if overlay_state.last_monitor_follow.is_some_and(|at| at.elapsed() < MONITOR_FOLLOW_DEBOUNCE) {
+        return;
     }
+    println!("[Rust] Moving overlay to the monitor under the cursor: {:?}", monitor.name());
+    overlay_state.last_monitor_follow = Some(Instant::now());
+    // Recorded now so the Moved event doesn't trigger another placement
+    overlay_state.current_monitor = monitor.name().cloned();
+    place_overlay_on(window, &window.state::<settings::SharedSettings>(), monitor);
+}
 
-    if changed {
-        println!("[Rust] Status update: listening={}, speaking={}, wake_word={}, text='{}', visible={}",
-                is_listening, is_speaking, wake_word_detected, current_text, should_be_visible);
+// Sizes and positions the overlay on `monitor`: a corner panel, fitted to its content or the whole screen
+fn place_overlay_on(window: &Window, settings: &settings::SharedSettings, monitor: &tauri::Monitor) {
+    if window.state::<PinnedWindow>().target.lock().unwrap().is_some() {
+        return;
+    }
+    let (position, compact, fit_to_content) = {
+        let settings = settings.lock().unwrap();
+        (settings.overlay.position.clone(), settings.overlay.display_mode == "compact", settings.overlay.fit_to_content)
+    };
+    let corner = placement::Corner::from_position(&position);
+    if compact {
+        // Compact mode always uses a corner, bottom-right unless one is configured
+        let corner = corner.unwrap_or(placement::Corner::BottomRight);
+        placement::apply_corner_placement(window, monitor, corner, placement::COMPACT_SIZE);
+        println!("Overlay set to compact mode in {:?} corner of monitor: {:?}", corner, monitor.name());
+    } else if let Some(corner) = corner {
+        placement::apply_corner_placement(window, monitor, corner, placement::PANEL_SIZE);
+        println!("Overlay set to {} corner of monitor: {:?}", position, monitor.name());
+    } else if fit_to_content {
+        // A window the size of the monitor can upset fullscreen games even when it's click-through
+        let content_size = window.state::<ContentSize>().0.lock().unwrap().unwrap_or(placement::FIT_MIN_SIZE);
+        let rect = placement::content_rect(*monitor.position(), *monitor.size(), monitor.scale_factor(), content_size);
+        placement::apply_rect(window, rect, monitor.scale_factor());
+        println!("Overlay fitted to its content ({}x{}) on monitor: {:?}", rect.size.width, rect.size.height, monitor.name());
+    } else {
+        window.set_size(monitor.size().to_logical::<u32>(monitor.scale_factor())).unwrap_or_else(|e| eprintln!("Failed to set window size: {}",e));
+        window.set_position(monitor.position().to_logical::<i32>(monitor.scale_factor())).unwrap_or_else(|e| eprintln!("Failed to set window position: {}",e));
+        println!("Overlay set to monitor: {:?}", monitor.name());
+    }
+}
 
-        state_guard.status = status.clone();
-        state_guard.text = current_text.clone();
-        state_guard.is_listening = is_listening;
-        state_guard.is_speaking = is_speaking;
-        state_guard.wake_word_detected = wake_word_detected;
+// Places the overlay on the primary monitor, or the one under the cursor with follow_active_monitor
+fn place_overlay(window: &Window, settings: &settings::SharedSettings) {
+    let follow_active_monitor = settings.lock().unwrap().overlay.follow_active_monitor;
+    let cursor_monitor = if follow_active_monitor { placement::monitor_at_cursor(window) } else { None };
+    let monitor = match cursor_monitor {
+        Some(monitor) => Ok(Some(monitor)),
+        None => window.primary_monitor(),
+    };
+    let reason = match monitor {
+        Ok(Some(monitor)) => {
+            window.state::<SharedState>().lock().unwrap().monitor_warning = None;
+            return place_overlay_on(window, settings, &monitor);
+        }
+        Ok(None) => "No primary monitor reported".to_string(),
+        Err(e) => format!("Error getting primary monitor: {}", e),
+    };
 
-        if should_be_visible && !state_guard.visible {
-            window.show().unwrap_or_else(|e| eprintln!("Failed to show window: {}", e));
-            state_guard.visible = true;
-        }
-        state_guard.visible = should_be_visible;        // Emit status update to frontend
-        let payload = StatusUpdate {
-            status: status.clone(),
-            text: state_guard.text.clone(),
-            is_listening: state_guard.is_listening,
-            is_speaking: state_guard.is_speaking,
-            wake_word_detected: state_guard.wake_word_detected,
-        };
-        window.emit("status-update", payload).unwrap_or_else(|e| {
-            eprintln!("Failed to emit status-update: {}", e);
-        });
-        state_guard.last_activity_time = Instant::now();
-    }    // Auto-hide logic - only hide after longer period and when truly inactive
-    if state_guard.visible && state_guard.last_activity_time.elapsed() > Duration::from_secs(30)
-        && current_text.is_empty() && !is_listening && !is_speaking && !wake_word_detected {
-        if window.is_visible().unwrap_or(false) {
-            println!("[Rust] Auto-hiding window due to prolonged inactivity and no relevant status.");
-            window.hide().unwrap_or_else(|e| eprintln!("Failed to hide window: {}", e));
-            state_guard.visible = false;
+    // Without a primary monitor the window would keep its tiny default size
+    let first_monitor = window.available_monitors().ok().and_then(|monitors| monitors.into_iter().next());
+    let message = match first_monitor {
+        Some(monitor) => {
+            place_overlay_on(window, settings, &monitor);
+            format!("{}, using monitor {:?}", reason, monitor.name())
         }
+        None => {
+            window.set_size(placement::FALLBACK_WINDOW_SIZE).unwrap_or_else(|e| eprintln!("Failed to set window size: {}", e));
+            window.set_position(tauri::LogicalPosition::new(0, 0)).unwrap_or_else(|e| eprintln!("Failed to set window position: {}", e));
+            format!("{} and no monitors found, using a default window size", reason)
+        }
+    };
+    eprintln!("[Rust] {}", message);
+    window.state::<SharedState>().lock().unwrap().monitor_warning = Some(message.clone());
+    window
+        .app_handle()
+        .emit_all("monitor-warning", message)
+        .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit monitor-warning: {}", e));
+}
+
+// Re-apply placement and click-through after a display change (docking, resolution or DPI change)
+fn refresh_overlay_placement(window: &Window) {
+    let settings = window.state::<settings::SharedSettings>();
+    place_overlay(window, &settings);
+    placement::ensure_on_screen(window);
+    // Don't re-force transparency while the user is interacting with the overlay
+    let interactive = window.state::<SharedState>().lock().unwrap().interactive;
+    set_click_through(window, !interactive);
+    remember_current_monitor(window);
+    // The window may be smaller now, so the offset may need clamping again
+    emit_content_offset(window);
+}
+
+// Returns true if the window is now on a different monitor than last time
+fn remember_current_monitor(window: &Window) -> bool {
+    let monitor_name = window.current_monitor().ok().flatten().and_then(|m| m.name().cloned());
+    let state = window.state::<SharedState>();
+    let mut state_guard = state.lock().unwrap();
+    if state_guard.current_monitor != monitor_name {
+        state_guard.current_monitor = monitor_name;
+        true
+    } else {
+        false
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Only one overlay may run; launching it again brings the running one forward instead
+    let instance_lock = match settings::get_settings_path().and_then(|path| {
+        let dir = path.parent().ok_or("Settings path has no directory")?;
+        instance::acquire(dir).map_err(|e| e.to_string())
+    }) {
+        Ok(instance::Instance::Primary(lock)) => Some(lock),
+        Ok(instance::Instance::AlreadyRunning) => {
+            println!("[Rust] Overlay is already running, activated it and exiting");
+            return;
+        }
+        Err(e) => {
+            eprintln!("[Rust] Single-instance check failed: {}, starting anyway", e);
+            None
+        }
+    };
+
     let state = Arc::new(Mutex::new(OverlayState::new()));
+    let settings: settings::SharedSettings = Arc::new(Mutex::new(settings::load_settings_from_disk()));
+
+    // Normal is what the process starts with, so only a raised priority needs applying
+    let process_priority = settings.lock().unwrap().process_priority.clone();
+    if let Some(level) = priority::Priority::from_setting(&process_priority).filter(|p| *p != priority::Priority::Normal) {
+        match priority::apply(level) {
+            Ok(()) => println!("[Rust] Process priority set to {}", process_priority),
+            Err(e) => eprintln!("[Rust] Process priority not changed: {}", e),
+        }
+    }
+
+    let allow_invalid_certs = settings.lock().unwrap().allow_invalid_certs;
 
     let app_result = tauri::Builder::default()
         .manage(state.clone())
+        .manage(settings.clone())
+        // Single HTTP client (one connection pool) shared by commands and the poll task
+        .manage(build_http_client(allow_invalid_certs))
+        .system_tray(build_system_tray())
+        .on_system_tray_event(handle_system_tray_event)
+        .manage(audio::MicMonitorState::default())
+        .manage(log_stream::LogStreamState::default())
+        .manage(ui_fallback::PageLoads::default())
+        .manage(OverlayWindow::default())
+        .manage(ContentSize::default())
+        .manage(PinnedWindow::default())
+        .manage(settings::SettingsWriter::default())
+        .on_page_load(|window, payload| watch_page_load(window, payload.url().to_string()))
         .setup(move |app| {
-            let main_window = app.get_window("main").unwrap();
+            // A renamed window in tauri.conf.json would otherwise only say which label is missing
+            let main_window = overlay_window(&app.handle()).map_err(|e| {
+                let mut labels: Vec<String> = app.windows().into_keys().collect();
+                labels.sort();
+                if labels.is_empty() {
+                    format!("{}, no windows are configured", e)
+                } else {
+                    format!("{}, the configured windows are: {}", e, labels.join(", "))
+                }
+            })?;
             let app_handle = app.handle();
             let state_clone_for_poll = state.clone();
+            let http_client = app.state::<reqwest::Client>().inner().clone();
 
-            // Get primary monitor and set window to its size and position
-            match main_window.primary_monitor() { // Changed from app.get_primary_monitor()
-                Ok(Some(monitor)) => {
-                    main_window.set_size(monitor.size().to_logical::<u32>(monitor.scale_factor())).unwrap_or_else(|e| eprintln!("Failed to set window size: {}",e));
-                    main_window.set_position(monitor.position().to_logical::<i32>(monitor.scale_factor())).unwrap_or_else(|e| eprintln!("Failed to set window position: {}",e));
-                    println!("Overlay set to primary monitor: {:?}", monitor.name());
-                }
-                Ok(None) => {
-                    eprintln!("Could not get primary monitor info.");
-                }
-                Err(e) => {
-                    eprintln!("Error getting primary monitor: {}", e);
-                }
-            }            set_click_through(&main_window, true);
+            if let Some(lock) = &instance_lock {
+                let activate_handle = app_handle.clone();
+                lock.on_activate(move || {
+                    println!("[Rust] Another launch asked the overlay to activate, opening settings");
+                    open_settings_window(&activate_handle)
+                        .unwrap_or_else(|e| eprintln!("[Rust] Failed to open settings window: {}", e));
+                });
+            }
+
+            place_overlay(&main_window, &settings);
+            placement::ensure_on_screen(&main_window);
+            set_click_through(&main_window, true);
+            remember_current_monitor(&main_window);
 
-            // Force show window for debugging
-            main_window.show().unwrap_or_else(|e| eprintln!("Failed to show window: {}", e));
+            // Force show window for debugging, unless the user turned the overlay off last session
+            let user_disabled = settings.lock().unwrap().overlay.user_disabled;
+            {
+                let mut state_guard = state.lock().unwrap();
+                state_guard.user_disabled = user_disabled;
+                if !user_disabled {
+                    set_overlay_visible(&main_window, &mut state_guard, true, "startup")
+                        .unwrap_or_else(|e| eprintln!("Failed to show window: {}", e));
+                }
+            }
+            if user_disabled {
+                println!("[Rust] Overlay was turned off by the user, staying hidden");
+            }
             // Remove focus call to prevent window from stealing focus
             // main_window.set_focus().unwrap_or_else(|e| eprintln!("Failed to focus window: {}", e));
 
-            tauri::async_runtime::spawn(async move {
-                poll_assistant_status(app_handle, state_clone_for_poll).await;
-            });
+            tauri::async_runtime::spawn(run_briefing_scheduler(http_client.clone(), app_handle.clone(), state.clone()));
+            tauri::async_runtime::spawn(run_dnd_watcher(app_handle.clone(), state.clone()));
+            tauri::async_runtime::spawn(run_topmost_reasserter(app_handle.clone(), state.clone()));
+            start_metrics_server(&settings, state.clone());
+            let writer_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move { writer_handle.state::<settings::SettingsWriter>().run().await });
+            tauri::async_runtime::spawn(supervise_poll_task(http_client, app_handle, state_clone_for_poll));
 
             Ok(())
         })        .invoke_handler(tauri::generate_handler![
             show_overlay,
             hide_overlay,
+            set_overlay_window,
+            get_overlay_window,
+            set_content_size,
+            pin_to_window,
+            unpin_window,
             update_status,
-            get_state
+            get_state,
+            get_full_state,
+            frontend_ready,
+            set_raw_logging,
+            restore_state,
+            get_diagnostics,
+            clear_overlay,
+            set_process_priority,
+            get_process_priority,
+            set_language,
+            apply_audio_selection,
+            run_self_test,
+            get_status_history,
+            export_diagnostics_bundle,
+            record_session,
+            stop_session_recording,
+            replay_session,
+            get_server_info,
+            get_available_wake_words,
+            update_voice_settings,
+            set_debug_state,
+            set_interactive,
+            toggle_overlay_display,
+            set_paused,
+            snooze_overlay,
+            set_z_order,
+            set_overlay_click_through,
+            is_click_through,
+            set_display_mode,
+            set_text_scale,
+            set_theme,
+            set_content_offset,
+            get_content_offset,
+            reconnect_now,
+            set_transport,
+            set_active_port,
+            list_candidate_servers,
+            inject_connection_fault,
+            trigger_briefing,
+            detect_location,
+            open_settings,
+            placement::get_overlay_bounds,
+            settings::load_settings,
+            settings::save_settings,
+            settings::get_effective_config,
+            settings::export_settings,
+            settings::import_settings,
+            settings::reset_settings,
+            audio::get_audio_devices,
+            audio::test_audio_device,
+            log_stream::start_log_stream,
+            start_sensitivity_tuning,
+            set_sensitivity_preview,
+            stop_sensitivity_tuning,
+            log_stream::stop_log_stream,
+            audio::start_mic_monitor,
+            audio::stop_mic_monitor
         ])
         .on_window_event(|event| {
             match event.event() {
@@ -390,9 +3596,34 @@ pub fn run() {
                         // set_click_through(event.window(), true);
                     }
                 }
-                WindowEvent::CloseRequested { api: _api, .. } => { // Silenced unused api
-                    // event.window().hide().unwrap();
-                    // _api.prevent_close();
+                // The settings window is only hidden, so reopening it is instant and keeps unsaved edits
+                WindowEvent::CloseRequested { api, .. } if event.window().label() == "settings" => {
+                    api.prevent_close();
+                    event.window().hide().unwrap_or_else(|e| eprintln!("[Rust] Failed to hide settings window: {}", e));
+                    // What the window started shouldn't keep running while nobody sees it
+                    let monitor = event.window().state::<audio::MicMonitorState>();
+                    audio::stop_mic_monitor_for_window(&monitor, event.window().label());
+                    tauri::async_runtime::spawn(stop_tuning_for_closed_window(event.window().app_handle()));
+                    if log_stream::stop(&event.window().state::<log_stream::LogStreamState>()) {
+                        event
+                            .window()
+                            .emit("server-log-stopped", "Settings window closed")
+                            .unwrap_or_else(|e| eprintln!("[Rust] Failed to emit server-log-stopped: {}", e));
+                    }
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } if is_overlay_window(event.window()) => {
+                    println!("[Rust] Scale factor changed to {}, re-applying overlay placement", scale_factor);
+                    refresh_overlay_placement(event.window());
+                }
+                // The OS relocates the overlay when its monitor disappears (e.g. undocking)
+                WindowEvent::Moved(_) if is_overlay_window(event.window()) && remember_current_monitor(event.window()) => {
+                    println!("[Rust] Overlay moved to another monitor, re-applying placement");
+                    refresh_overlay_placement(event.window());
+                }
+                WindowEvent::Destroyed => {
+                    // Release the microphone if the window that started the meter goes away
+                    let monitor = event.window().state::<audio::MicMonitorState>();
+                    audio::stop_mic_monitor_for_window(&monitor, event.window().label());
                 }
                 _ => {}
             }
@@ -401,10 +3632,14 @@ pub fn run() {
 
     match app_result {
         Ok(app) => {
-            app.run(|_app_handle, event| match event {
+            app.run(|app_handle, event| match event {
                 tauri::RunEvent::ExitRequested { api, .. } => {
                     api.prevent_exit();
                 }
+                tauri::RunEvent::Exit => {
+                    app_handle.state::<settings::SettingsWriter>().flush();
+                    notify_overlay_shutdown(app_handle, "exit");
+                }
                 _ => {}
             });
         }
@@ -414,11 +3649,16 @@ pub fn run() {
     }
 }
 
+// `click_through` is what the caller wants (false while interactive); it only takes effect
+// if OverlaySettings.click_through is on
 fn set_click_through(window: &Window, click_through: bool) {
+    let requested = click_through;
+    let click_through = requested && window.state::<settings::SharedSettings>().lock().unwrap().overlay.click_through;
     #[cfg(target_os = "windows")]
     {
         use windows_sys::Win32::UI::WindowsAndMessaging::{
-            WS_EX_TRANSPARENT, WS_EX_LAYERED, GWL_EXSTYLE, SetWindowLongPtrW, GetWindowLongPtrW
+            WS_EX_TRANSPARENT, WS_EX_LAYERED, GWL_EXSTYLE, SetWindowLongPtrW, GetWindowLongPtrW,
+            SetWindowPos, SWP_NOMOVE, SWP_NOSIZE, SWP_NOACTIVATE, SWP_NOZORDER, SWP_FRAMECHANGED
         };
         // HWND import is now in get_hwnd
 
@@ -433,8 +3673,10 @@ fn set_click_through(window: &Window, click_through: bool) {
                     if click_through {
                         SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_TRANSPARENT as isize | WS_EX_LAYERED as isize);
                     } else {
-                        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style & !(WS_EX_TRANSPARENT as isize) & !(WS_EX_LAYERED as isize));
+                        // Keep WS_EX_LAYERED so the window stays transparent, only accept input again
+                        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style & !(WS_EX_TRANSPARENT as isize));
                     }
+                    SetWindowPos(hwnd, 0, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE | SWP_NOZORDER | SWP_FRAMECHANGED);
                 }
             }
             Err(e) => {
@@ -446,6 +3688,45 @@ fn set_click_through(window: &Window, click_through: bool) {
     {
         println!("Click-through not implemented for this OS");
     }
+    // While interactive the Z-order is left alone so the overlay doesn't bounce under the
+    // window the user is clicking from
+    if requested {
+        apply_z_order(window);
+    }
+}
+
+fn current_z_order(window: &Window) -> placement::ZOrder {
+    let z_order_setting = window.state::<settings::SharedSettings>().lock().unwrap().overlay.z_order.clone();
+    placement::ZOrder::from_setting(&z_order_setting).unwrap_or(placement::ZOrder::Top)
+}
+
+// Stacks the overlay according to OverlaySettings.z_order; this is the only place that sets it
+fn apply_z_order(window: &Window) {
+    let z_order = current_z_order(window);
+    window
+        .set_always_on_top(z_order == placement::ZOrder::Top)
+        .unwrap_or_else(|e| eprintln!("Failed to set always on top: {}", e));
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{HWND_BOTTOM, HWND_TOPMOST};
+        // set_always_on_top does nothing if the flag didn't change, but a fullscreen game may
+        // have pushed the overlay down anyway, so "top" is re-stacked explicitly too. Queued
+        // behind set_always_on_top, so the topmost flag is already set or cleared when this runs.
+        let insert_after = if z_order == placement::ZOrder::Top { HWND_TOPMOST } else { HWND_BOTTOM };
+        let target = window.clone();
+        window
+            .run_on_main_thread(move || {
+                use windows_sys::Win32::UI::WindowsAndMessaging::{SetWindowPos, SWP_NOMOVE, SWP_NOSIZE, SWP_NOACTIVATE};
+                match get_hwnd(&target) {
+                    Ok(hwnd) => unsafe {
+                        SetWindowPos(hwnd, insert_after, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE);
+                    },
+                    Err(e) => eprintln!("Could not get HWND for z-order: {}", e),
+                }
+            })
+            .unwrap_or_else(|e| eprintln!("Failed to apply z-order: {}", e));
+    }
 }
 
 // Helper function to extract HWND
@@ -0,0 +1,192 @@
+// Overlay metrics in the Prometheus text format, served on a small HTTP endpoint for kiosk
+// deployments that are monitored centrally (Settings.metrics)
+
+use std::fmt::Write as _;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// Upper bounds in seconds; requests to a local client normally take a few milliseconds
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+// A scraper sends a short GET; anything bigger or slower isn't one
+const MAX_REQUEST_BYTES: usize = 8192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    // Per bucket of LATENCY_BUCKETS, not cumulative
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram { counts: vec![0; LATENCY_BUCKETS.len()], sum: 0.0, count: 0 }
+    }
+}
+
+impl Histogram {
+    pub fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.counts[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Values for one scrape, taken from the overlay state.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub status_updates: u64,
+    pub reconnects: u64,
+    pub poll_task_restarts: u32,
+    pub connected: bool,
+    /// "sse" or "polling", None while not connected
+    pub transport: Option<&'static str>,
+    pub notification_queue_length: usize,
+    pub poll_latency: Histogram,
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP gaja_overlay_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE gaja_overlay_{} {}", name, kind);
+}
+
+pub fn render(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    metric(&mut out, "status_updates_total", "counter", "Status payloads received from the client.");
+    let _ = writeln!(out, "gaja_overlay_status_updates_total {}", snapshot.status_updates);
+    metric(&mut out, "reconnects_total", "counter", "Connection cycles that ended and were retried.");
+    let _ = writeln!(out, "gaja_overlay_reconnects_total {}", snapshot.reconnects);
+    metric(&mut out, "poll_task_restarts_total", "counter", "Times the watchdog relaunched the connection loop.");
+    let _ = writeln!(out, "gaja_overlay_poll_task_restarts_total {}", snapshot.poll_task_restarts);
+    metric(&mut out, "connected", "gauge", "1 while the overlay is connected to the client.");
+    let _ = writeln!(out, "gaja_overlay_connected {}", u8::from(snapshot.connected));
+    metric(&mut out, "transport", "gauge", "1 for the transport in use.");
+    for transport in ["sse", "polling"] {
+        let active = snapshot.transport == Some(transport);
+        let _ = writeln!(out, "gaja_overlay_transport{{transport=\"{}\"}} {}", transport, u8::from(active));
+    }
+    metric(&mut out, "notification_queue_length", "gauge", "Notifications waiting to be shown.");
+    let _ = writeln!(out, "gaja_overlay_notification_queue_length {}", snapshot.notification_queue_length);
+
+    let latency = &snapshot.poll_latency;
+    metric(&mut out, "poll_latency_seconds", "histogram", "Duration of status requests in polling mode.");
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(&latency.counts) {
+        cumulative += count;
+        let _ = writeln!(out, "gaja_overlay_poll_latency_seconds_bucket{{le=\"{}\"}} {}", bound, cumulative);
+    }
+    let _ = writeln!(out, "gaja_overlay_poll_latency_seconds_bucket{{le=\"+Inf\"}} {}", latency.count);
+    let _ = writeln!(out, "gaja_overlay_poll_latency_seconds_sum {}", latency.sum);
+    let _ = writeln!(out, "gaja_overlay_poll_latency_seconds_count {}", latency.count);
+    out
+}
+
+// Full HTTP response for a request head; only GET /metrics is served
+fn respond(request: &str, snapshot: impl FnOnce() -> Snapshot) -> String {
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", render(&snapshot())),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found, try /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string()),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Serves /metrics on `address:port` until the app exits.
+pub async fn serve<F>(address: IpAddr, port: u16, snapshot: F) -> std::io::Result<()>
+where
+    F: Fn() -> Snapshot + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(SocketAddr::new(address, port)).await?;
+    println!("[Rust] Metrics available at http://{}/metrics", listener.local_addr()?);
+    let snapshot = Arc::new(snapshot);
+    loop {
+        let (mut stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("[Rust] Metrics connection failed: {}", e);
+                continue;
+            }
+        };
+        let snapshot = snapshot.clone();
+        tokio::spawn(async move {
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            let read_head = async {
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+            };
+            if tokio::time::timeout(REQUEST_TIMEOUT, read_head).await.is_err() {
+                return;
+            }
+            let response = respond(&String::from_utf8_lossy(&request), || snapshot());
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_gauges_and_a_cumulative_histogram() {
+        let mut poll_latency = Histogram::default();
+        poll_latency.observe(Duration::from_millis(3));
+        poll_latency.observe(Duration::from_millis(40));
+        poll_latency.observe(Duration::from_secs(9));
+        let snapshot = Snapshot {
+            status_updates: 12,
+            reconnects: 2,
+            connected: true,
+            transport: Some("polling"),
+            poll_latency,
+            ..Snapshot::default()
+        };
+        let text = render(&snapshot);
+        assert!(text.contains("# TYPE gaja_overlay_status_updates_total counter\ngaja_overlay_status_updates_total 12\n"));
+        assert!(text.contains("gaja_overlay_reconnects_total 2\n"));
+        assert!(text.contains("gaja_overlay_connected 1\n"));
+        assert!(text.contains("gaja_overlay_transport{transport=\"sse\"} 0\n"));
+        assert!(text.contains("gaja_overlay_transport{transport=\"polling\"} 1\n"));
+        assert!(text.contains("gaja_overlay_poll_latency_seconds_bucket{le=\"0.005\"} 1\n"));
+        assert!(text.contains("gaja_overlay_poll_latency_seconds_bucket{le=\"0.05\"} 2\n"));
+        // 9 seconds is past the last bound and only counts in +Inf
+        assert!(text.contains("gaja_overlay_poll_latency_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(text.contains("gaja_overlay_poll_latency_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("gaja_overlay_poll_latency_seconds_count 3\n"));
+    }
+
+    #[test]
+    fn only_get_metrics_is_served() {
+        let ok = respond("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n", Snapshot::default);
+        assert!(ok.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(ok.contains("gaja_overlay_connected 0"));
+        let body_length = ok.split("\r\n\r\n").nth(1).unwrap().len();
+        assert!(ok.contains(&format!("Content-Length: {}\r\n", body_length)));
+
+        assert!(respond("GET / HTTP/1.1\r\n\r\n", Snapshot::default).starts_with("HTTP/1.1 404"));
+        assert!(respond("POST /metrics HTTP/1.1\r\n\r\n", Snapshot::default).starts_with("HTTP/1.1 405"));
+        assert!(respond("", Snapshot::default).starts_with("HTTP/1.1 405"));
+    }
+}
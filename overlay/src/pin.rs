@@ -0,0 +1,159 @@
+// Pinning the overlay over one application window (e.g. OBS while streaming) instead of a
+// monitor. Windows only: the target is found by its title and followed by polling its rect.
+
+use tauri::{PhysicalPosition, PhysicalSize};
+
+/// The window the overlay is pinned to. `hwnd` is a Win32 HWND.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinTarget {
+    pub hwnd: isize,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetState {
+    Closed,
+    Minimized,
+    // Only found by target_state on Windows
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    At(PhysicalPosition<i32>, PhysicalSize<u32>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinAction {
+    None,
+    /// The target is gone
+    Unpin,
+    /// The target is minimized; the overlay shouldn't float over nothing
+    Hide,
+    /// Move the overlay onto the target; `restored` after it was minimized
+    Place {
+        position: PhysicalPosition<i32>,
+        size: PhysicalSize<u32>,
+        restored: bool,
+    },
+}
+
+/// What to do with the overlay after the target went from `previous` (None on the first check) to `current`.
+pub fn next_action(previous: Option<TargetState>, current: TargetState) -> PinAction {
+    match current {
+        TargetState::Closed => PinAction::Unpin,
+        // Repeated while minimized, so a status update that shows the overlay is undone
+        TargetState::Minimized => PinAction::Hide,
+        TargetState::At(..) if previous == Some(current) => PinAction::None,
+        TargetState::At(position, size) => PinAction::Place {
+            position,
+            size,
+            restored: previous == Some(TargetState::Minimized),
+        },
+    }
+}
+
+fn title_matches(title: &str, title_substring: &str) -> bool {
+    title.to_lowercase().contains(&title_substring.to_lowercase())
+}
+
+/// First visible top-level window of another process whose title contains `title_substring` (any case).
+pub fn find_window(title_substring: &str) -> Result<PinTarget, String> {
+    let title_substring = title_substring.trim();
+    if title_substring.is_empty() {
+        return Err("Window title to pin to is empty".to_string());
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            EnumWindows, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+        };
+
+        unsafe extern "system" fn collect(hwnd: HWND, windows: LPARAM) -> BOOL {
+            let windows = &mut *(windows as *mut Vec<(HWND, String)>);
+            let length = GetWindowTextLengthW(hwnd);
+            if IsWindowVisible(hwnd) == 0 || length == 0 {
+                return 1;
+            }
+            let mut process_id = 0;
+            GetWindowThreadProcessId(hwnd, &mut process_id);
+            if process_id == std::process::id() {
+                return 1;
+            }
+            let mut buffer = vec![0u16; length as usize + 1];
+            let copied = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+            windows.push((hwnd, String::from_utf16_lossy(&buffer[..copied.max(0) as usize])));
+            1
+        }
+
+        let mut windows: Vec<(HWND, String)> = Vec::new();
+        unsafe {
+            EnumWindows(Some(collect), &mut windows as *mut _ as LPARAM);
+        }
+        windows
+            .into_iter()
+            .find(|(_, title)| title_matches(title, title_substring))
+            .map(|(hwnd, title)| PinTarget { hwnd, title })
+            .ok_or_else(|| format!("No window with \"{}\" in its title", title_substring))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = title_matches;
+        Err("Pinning to a window is only supported on Windows".to_string())
+    }
+}
+
+/// Where the target window is now, in physical screen coordinates.
+pub fn target_state(target: &PinTarget) -> TargetState {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::RECT;
+        use windows_sys::Win32::UI::WindowsAndMessaging::{GetWindowRect, IsIconic, IsWindow};
+
+        let hwnd = target.hwnd;
+        unsafe {
+            if IsWindow(hwnd) == 0 {
+                return TargetState::Closed;
+            }
+            if IsIconic(hwnd) != 0 {
+                return TargetState::Minimized;
+            }
+            let mut rect = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+            if GetWindowRect(hwnd, &mut rect) == 0 {
+                return TargetState::Closed;
+            }
+            TargetState::At(
+                PhysicalPosition::new(rect.left, rect.top),
+                PhysicalSize::new((rect.right - rect.left).max(1) as u32, (rect.bottom - rect.top).max(1) as u32),
+            )
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = target;
+        TargetState::Closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: i32, width: u32) -> TargetState {
+        TargetState::At(PhysicalPosition::new(x, 0), PhysicalSize::new(width, 600))
+    }
+
+    #[test]
+    fn overlay_follows_the_target_and_hides_while_it_is_minimized() {
+        assert!(matches!(next_action(None, at(0, 800)), PinAction::Place { restored: false, .. }));
+        assert_eq!(next_action(Some(at(0, 800)), at(0, 800)), PinAction::None);
+        assert!(matches!(next_action(Some(at(0, 800)), at(50, 800)), PinAction::Place { restored: false, .. }));
+        assert_eq!(next_action(Some(at(50, 800)), TargetState::Minimized), PinAction::Hide);
+        assert_eq!(next_action(Some(TargetState::Minimized), TargetState::Minimized), PinAction::Hide);
+        assert!(matches!(next_action(Some(TargetState::Minimized), at(50, 800)), PinAction::Place { restored: true, .. }));
+        assert_eq!(next_action(Some(at(50, 800)), TargetState::Closed), PinAction::Unpin);
+    }
+
+    #[test]
+    fn title_match_ignores_case() {
+        assert!(title_matches("OBS 30.1.2 - Profile: Stream", "obs"));
+        assert!(!title_matches("Discord", "obs"));
+    }
+}
@@ -0,0 +1,368 @@
+// Overlay window placement helpers (corner panels on mixed-DPI monitor setups)
+
+use serde::Serialize;
+use tauri::{AppHandle, LogicalPosition, LogicalSize, Monitor, PhysicalPosition, PhysicalSize, Window};
+
+// Desired corner panel sizes and distance from the screen edges, in physical pixels
+pub const PANEL_SIZE: PhysicalSize<u32> = PhysicalSize { width: 480, height: 180 };
+// Compact display mode: just the status ball
+pub const COMPACT_SIZE: PhysicalSize<u32> = PhysicalSize { width: 120, height: 120 };
+const PANEL_MARGIN: u32 = 24;
+// Window size when no monitor is reported at all; the window goes to the top-left of the desktop
+pub const FALLBACK_WINDOW_SIZE: LogicalSize<f64> = LogicalSize { width: 1280.0, height: 720.0 };
+// Area the full display mode content takes up, anchored at the top centre of the window (CSS
+// pixels). Mirrors the width and height of `.overlay-background` in style.css; change both together.
+const CONTENT_SIZE: LogicalSize<f64> = LogicalSize { width: 600.0, height: 350.0 };
+// With OverlaySettings.fit_to_content, the window size until the frontend reports its content.
+// Some platforms refuse a zero-sized window.
+pub const FIT_MIN_SIZE: LogicalSize<f64> = LogicalSize { width: 1.0, height: 1.0 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// Maps an `OverlaySettings.position` value to a corner, `None` for fullscreen/unknown.
+    pub fn from_position(position: &str) -> Option<Corner> {
+        match position {
+            "top-left" => Some(Corner::TopLeft),
+            "top-right" => Some(Corner::TopRight),
+            "bottom-left" => Some(Corner::BottomLeft),
+            "bottom-right" => Some(Corner::BottomRight),
+            _ => None,
+        }
+    }
+}
+
+/// Stacking of the overlay relative to other windows (`OverlaySettings.z_order`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZOrder {
+    /// Above all windows, including fullscreen games
+    Top,
+    /// Behind normal windows, as ambient information on the desktop
+    Bottom,
+}
+
+impl ZOrder {
+    pub fn from_setting(value: &str) -> Option<ZOrder> {
+        match value {
+            "top" => Some(ZOrder::Top),
+            "bottom" => Some(ZOrder::Bottom),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelRect {
+    pub position: LogicalPosition<f64>,
+    pub size: LogicalSize<f64>,
+}
+
+/// Computes the panel rect in the target monitor's logical coordinates (physical / scale_factor).
+///
+/// Converting the result back with the same scale factor gives exact physical coordinates,
+/// so the panel lands correctly even when the window currently sits on a monitor with a
+/// different DPI. Panels larger than the monitor are shrunk so they always fit on-screen.
+pub fn corner_panel_rect(
+    monitor_position: PhysicalPosition<i32>,
+    monitor_size: PhysicalSize<u32>,
+    scale_factor: f64,
+    panel_size: PhysicalSize<u32>,
+    corner: Corner,
+) -> PanelRect {
+    let scale = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+
+    let monitor_x = monitor_position.x as f64 / scale;
+    let monitor_y = monitor_position.y as f64 / scale;
+    let monitor_width = monitor_size.width as f64 / scale;
+    let monitor_height = monitor_size.height as f64 / scale;
+
+    let width = (panel_size.width as f64 / scale).min(monitor_width);
+    let height = (panel_size.height as f64 / scale).min(monitor_height);
+    let margin_x = (PANEL_MARGIN as f64 / scale).min((monitor_width - width) / 2.0);
+    let margin_y = (PANEL_MARGIN as f64 / scale).min((monitor_height - height) / 2.0);
+
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => monitor_x + margin_x,
+        Corner::TopRight | Corner::BottomRight => monitor_x + monitor_width - width - margin_x,
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => monitor_y + margin_y,
+        Corner::BottomLeft | Corner::BottomRight => monitor_y + monitor_height - height - margin_y,
+    };
+
+    PanelRect {
+        position: LogicalPosition::new(x, y),
+        size: LogicalSize::new(width, height),
+    }
+}
+
+/// Rect just around the full mode content (`content_size`, CSS pixels) at the top centre of
+/// the monitor, where the content is drawn in a fullscreen window. Coordinates as in
+/// `corner_panel_rect`; the size is limited to the monitor and at least `FIT_MIN_SIZE`.
+pub fn content_rect(
+    monitor_position: PhysicalPosition<i32>,
+    monitor_size: PhysicalSize<u32>,
+    scale_factor: f64,
+    content_size: LogicalSize<f64>,
+) -> PanelRect {
+    let scale = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+
+    let monitor_x = monitor_position.x as f64 / scale;
+    let monitor_y = monitor_position.y as f64 / scale;
+    let monitor_width = monitor_size.width as f64 / scale;
+    let monitor_height = monitor_size.height as f64 / scale;
+
+    let width = content_size.width.ceil().clamp(FIT_MIN_SIZE.width, monitor_width.max(FIT_MIN_SIZE.width));
+    let height = content_size.height.ceil().clamp(FIT_MIN_SIZE.height, monitor_height.max(FIT_MIN_SIZE.height));
+    PanelRect {
+        position: LogicalPosition::new(monitor_x + ((monitor_width - width) / 2.0).floor(), monitor_y),
+        size: LogicalSize::new(width, height),
+    }
+}
+
+/// Shrinks the overlay into a `panel_size` panel in the given corner of `monitor`.
+pub fn apply_corner_placement(window: &Window, monitor: &Monitor, corner: Corner, panel_size: PhysicalSize<u32>) {
+    let scale = monitor.scale_factor();
+    let rect = corner_panel_rect(*monitor.position(), *monitor.size(), scale, panel_size, corner);
+    apply_rect(window, rect, scale);
+}
+
+/// Moves and resizes the overlay to `rect`, given in the logical coordinates of a monitor with `scale`.
+pub fn apply_rect(window: &Window, rect: PanelRect, scale: f64) {
+    // A maximized window ignores size/position changes on some platforms
+    window.unmaximize().unwrap_or_else(|e| eprintln!("Failed to unmaximize window: {}", e));
+    // Apply in physical units so the window's current monitor DPI doesn't skew the result
+    window.set_position(rect.position.to_physical::<i32>(scale)).unwrap_or_else(|e| eprintln!("Failed to set window position: {}", e));
+    window.set_size(rect.size.to_physical::<u32>(scale)).unwrap_or_else(|e| eprintln!("Failed to set window size: {}", e));
+}
+
+/// Whether two physical rects overlap by at least one pixel.
+pub fn rects_intersect(
+    a_position: PhysicalPosition<i32>,
+    a_size: PhysicalSize<u32>,
+    b_position: PhysicalPosition<i32>,
+    b_size: PhysicalSize<u32>,
+) -> bool {
+    let (a_left, a_top) = (a_position.x as i64, a_position.y as i64);
+    let (b_left, b_top) = (b_position.x as i64, b_position.y as i64);
+    a_left < b_left + b_size.width as i64
+        && b_left < a_left + a_size.width as i64
+        && a_top < b_top + b_size.height as i64
+        && b_top < a_top + a_size.height as i64
+}
+
+/// Whether the physical rect contains `point`.
+pub fn rect_contains(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, point: PhysicalPosition<i32>) -> bool {
+    let (left, top) = (position.x as i64, position.y as i64);
+    let (x, y) = (point.x as i64, point.y as i64);
+    left <= x && x < left + size.width as i64 && top <= y && y < top + size.height as i64
+}
+
+/// Mouse cursor position in physical screen coordinates, None where that isn't supported.
+pub fn cursor_position() -> Option<PhysicalPosition<i32>> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::POINT;
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetCursorPos;
+        let mut point = POINT { x: 0, y: 0 };
+        if unsafe { GetCursorPos(&mut point) } != 0 {
+            return Some(PhysicalPosition::new(point.x, point.y));
+        }
+        None
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+/// The monitor under the mouse cursor.
+pub fn monitor_at_cursor(window: &Window) -> Option<Monitor> {
+    let cursor = cursor_position()?;
+    window
+        .available_monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|m| rect_contains(*m.position(), *m.size(), cursor))
+}
+
+/// Moves the window back onto the primary monitor if it doesn't overlap any monitor
+/// (e.g. its monitor was unplugged). Returns true if the window was moved.
+pub fn ensure_on_screen(window: &Window) -> bool {
+    let (position, size) = match (window.outer_position(), window.outer_size()) {
+        (Ok(position), Ok(size)) => (position, size),
+        _ => return false,
+    };
+    let monitors = window.available_monitors().unwrap_or_default();
+    if monitors.is_empty()
+        || monitors.iter().any(|m| rects_intersect(position, size, *m.position(), *m.size()))
+    {
+        return false;
+    }
+
+    let primary = match window.primary_monitor() {
+        Ok(Some(monitor)) => monitor,
+        _ => monitors[0].clone(),
+    };
+    eprintln!(
+        "[Rust] Overlay at {:?} is off all screens, moving it to monitor {:?}",
+        position,
+        primary.name()
+    );
+    let fitted = PhysicalSize::new(size.width.min(primary.size().width), size.height.min(primary.size().height));
+    if fitted != size {
+        window.set_size(fitted).unwrap_or_else(|e| eprintln!("Failed to set window size: {}", e));
+    }
+    window.set_position(*primary.position()).unwrap_or_else(|e| eprintln!("Failed to set window position: {}", e));
+    true
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    name: Option<String>,
+    scale_factor: f64,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+}
+
+impl MonitorInfo {
+    fn from_monitor(monitor: &Monitor) -> Self {
+        MonitorInfo {
+            name: monitor.name().cloned(),
+            scale_factor: monitor.scale_factor(),
+            position: *monitor.position(),
+            size: *monitor.size(),
+        }
+    }
+}
+
+/// Where the overlay window actually is, in physical pixels.
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayBounds {
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    /// None if the window isn't on any monitor (e.g. after one was unplugged)
+    monitor: Option<MonitorInfo>,
+}
+
+#[tauri::command]
+pub fn get_overlay_bounds(app_handle: AppHandle) -> Result<OverlayBounds, String> {
+    let window = crate::overlay_window(&app_handle)?;
+    Ok(OverlayBounds {
+        position: window.outer_position().map_err(|e| e.to_string())?,
+        size: window.outer_size().map_err(|e| e.to_string())?,
+        monitor: window
+            .current_monitor()
+            .map_err(|e| e.to_string())?
+            .map(|m| MonitorInfo::from_monitor(&m)),
+    })
+}
+
+/// Limits a content offset (CSS pixels) so the content stays inside a window of `window_size`.
+/// Content starts at the top edge, so it can only move down.
+pub fn clamp_content_offset(x: i32, y: i32, window_size: LogicalSize<f64>) -> (i32, i32) {
+    let max_x = ((window_size.width - CONTENT_SIZE.width) / 2.0).max(0.0) as i32;
+    let max_y = (window_size.height - CONTENT_SIZE.height).max(0.0) as i32;
+    (x.clamp(-max_x, max_x), y.clamp(0, max_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_rect_is_centred_at_the_top_and_fits_the_monitor() {
+        let monitor_position = PhysicalPosition::new(1920, 0);
+        let monitor_size = PhysicalSize::new(2880, 1620);
+        let rect = content_rect(monitor_position, monitor_size, 1.5, LogicalSize::new(500.4, 300.0));
+        let (left, top, right, bottom) = physical_bounds(rect, 1.5);
+        assert_eq!(top, 0);
+        assert_eq!(bottom, 450);
+        // Rounded up to whole CSS pixels so the content isn't cut off
+        assert_eq!(rect.size.width, 501.0);
+        // Centred: the same distance to both monitor edges, give or take rounding
+        assert!(((left - 1920) - (1920 + 2880 - right)).abs() <= 2);
+
+        // Nothing to show yet, and content larger than the monitor
+        let empty = content_rect(monitor_position, monitor_size, 1.5, LogicalSize::new(0.0, 0.0));
+        assert_eq!(empty.size, FIT_MIN_SIZE);
+        let huge = content_rect(monitor_position, monitor_size, 1.5, LogicalSize::new(5000.0, 5000.0));
+        assert_eq!(physical_bounds(huge, 1.5), (1920, 0, 1920 + 2880, 1620));
+    }
+
+    fn physical_bounds(rect: PanelRect, scale: f64) -> (i32, i32, i32, i32) {
+        let pos = rect.position.to_physical::<i32>(scale);
+        let size = rect.size.to_physical::<u32>(scale);
+        (pos.x, pos.y, pos.x + size.width as i32, pos.y + size.height as i32)
+    }
+
+    #[test]
+    fn bottom_right_panel_stays_on_scaled_secondary_monitor() {
+        // 150% scaled monitor to the right of a 1920px primary
+        let monitor_position = PhysicalPosition::new(1920, 0);
+        let monitor_size = PhysicalSize::new(2880, 1620);
+        let rect = corner_panel_rect(monitor_position, monitor_size, 1.5, PANEL_SIZE, Corner::BottomRight);
+
+        let (left, top, right, bottom) = physical_bounds(rect, 1.5);
+        assert!(left >= 1920 && top >= 0);
+        assert!(right <= 1920 + 2880 && bottom <= 1620);
+        assert_eq!((right - left, bottom - top), (480, 180));
+    }
+
+    #[test]
+    fn every_corner_fits_on_scaled_monitor() {
+        let monitor_position = PhysicalPosition::new(-2880, 200);
+        let monitor_size = PhysicalSize::new(2880, 1620);
+        for corner in [Corner::TopLeft, Corner::TopRight, Corner::BottomLeft, Corner::BottomRight] {
+            let rect = corner_panel_rect(monitor_position, monitor_size, 1.5, PANEL_SIZE, corner);
+            let (left, top, right, bottom) = physical_bounds(rect, 1.5);
+            assert!(left >= -2880 && right <= 0, "{:?} out of horizontal bounds", corner);
+            assert!(top >= 200 && bottom <= 200 + 1620, "{:?} out of vertical bounds", corner);
+        }
+    }
+
+    #[test]
+    fn window_past_the_last_monitor_does_not_intersect() {
+        let monitor_position = PhysicalPosition::new(0, 0);
+        let monitor_size = PhysicalSize::new(1920, 1080);
+        let window_size = PhysicalSize::new(480, 180);
+        // Left behind where an unplugged second monitor used to be
+        assert!(!rects_intersect(PhysicalPosition::new(1920, 0), window_size, monitor_position, monitor_size));
+        assert!(!rects_intersect(PhysicalPosition::new(-480, 500), window_size, monitor_position, monitor_size));
+        assert!(rects_intersect(PhysicalPosition::new(1919, 1079), window_size, monitor_position, monitor_size));
+    }
+
+    #[test]
+    fn panel_is_shrunk_on_tiny_monitor() {
+        let rect = corner_panel_rect(PhysicalPosition::new(0, 0), PhysicalSize::new(400, 150), 1.0, PANEL_SIZE, Corner::TopLeft);
+        let (left, top, right, bottom) = physical_bounds(rect, 1.0);
+        assert_eq!((left, top, right, bottom), (0, 0, 400, 150));
+    }
+
+    #[test]
+    fn rect_contains_excludes_the_far_edges() {
+        // Second monitor to the left of the primary one
+        let position = PhysicalPosition::new(-1920, 0);
+        let size = PhysicalSize::new(1920, 1080);
+        assert!(rect_contains(position, size, PhysicalPosition::new(-1920, 0)));
+        assert!(rect_contains(position, size, PhysicalPosition::new(-1, 1079)));
+        assert!(!rect_contains(position, size, PhysicalPosition::new(0, 500)));
+        assert!(!rect_contains(position, size, PhysicalPosition::new(-100, 1080)));
+    }
+
+    #[test]
+    fn content_offset_keeps_content_in_the_window() {
+        let fullscreen = LogicalSize::new(1920.0, 1080.0);
+        assert_eq!(clamp_content_offset(-200, 40, fullscreen), (-200, 40));
+        assert_eq!(clamp_content_offset(5000, 5000, fullscreen), (660, 730));
+        assert_eq!(clamp_content_offset(-5000, -20, fullscreen), (-660, 0));
+        // A corner panel has no room to move the content at all
+        assert_eq!(clamp_content_offset(100, 100, LogicalSize::new(480.0, 180.0)), (0, 0));
+    }
+}
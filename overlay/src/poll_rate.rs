@@ -0,0 +1,65 @@
+// Status request interval in polling mode (Settings.polling). Fast while the assistant is
+// listening, speaking or was just woken; once it has been quiet for a while the interval
+// doubles on every request until it reaches the idle interval.
+
+use std::time::{Duration, Instant};
+
+use crate::settings::PollingSettings;
+
+#[derive(Debug, Clone)]
+pub struct PollRate {
+    fast: Duration,
+    idle: Duration,
+    idle_after: Duration,
+    current: Duration,
+    last_activity: Option<Instant>,
+}
+
+impl PollRate {
+    /// Starts at the idle interval; the first active payload speeds it up.
+    pub fn new(settings: &PollingSettings) -> Self {
+        let idle = Duration::from_millis(settings.idle_poll_ms);
+        PollRate {
+            fast: Duration::from_millis(settings.fast_poll_ms),
+            idle,
+            idle_after: Duration::from_millis(settings.idle_after_ms),
+            current: idle,
+            last_activity: None,
+        }
+    }
+
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    /// Interval until the next request, after a payload that showed the assistant `active` or not.
+    pub fn next(&mut self, active: bool, now: Instant) -> Duration {
+        if active {
+            self.last_activity = Some(now);
+            self.current = self.fast;
+        } else if self.last_activity.is_none_or(|at| now.saturating_duration_since(at) >= self.idle_after) {
+            self.current = (self.current * 2).min(self.idle);
+        }
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_while_active_then_back_to_idle_after_a_quiet_period() {
+        let settings = PollingSettings { fast_poll_ms: 100, idle_poll_ms: 1000, idle_after_ms: 5000 };
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut rate = PollRate::new(&settings);
+        assert_eq!(rate.next(false, at(0)), Duration::from_millis(1000));
+
+        assert_eq!(rate.next(true, at(1000)), Duration::from_millis(100));
+        // Still within idle_after of the last activity
+        assert_eq!(rate.next(false, at(5900)), Duration::from_millis(100));
+        let ramp: Vec<u128> = [6000, 6200, 6600, 7400, 8400].iter().map(|ms| rate.next(false, at(*ms)).as_millis()).collect();
+        assert_eq!(ramp, [200, 400, 800, 1000, 1000]);
+    }
+}
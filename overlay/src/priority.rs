@@ -0,0 +1,85 @@
+// Process priority, so status updates stay timely while a game or a build keeps the CPU busy
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Normal,
+    AboveNormal,
+}
+
+impl Priority {
+    /// Maps a `Settings.process_priority` value ("normal", "above_normal").
+    pub fn from_setting(value: &str) -> Option<Priority> {
+        match value {
+            "normal" => Some(Priority::Normal),
+            "above_normal" => Some(Priority::AboveNormal),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the priority class of the overlay process. Windows only.
+pub fn apply(priority: Priority) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentProcess, SetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+        };
+
+        let class = match priority {
+            Priority::Normal => NORMAL_PRIORITY_CLASS,
+            Priority::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+        };
+        // The pseudo handle from GetCurrentProcess doesn't need to be closed
+        if unsafe { SetPriorityClass(GetCurrentProcess(), class) } == 0 {
+            return Err(format!("SetPriorityClass failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = priority;
+        Err("Process priority can only be changed on Windows".to_string())
+    }
+}
+
+/// The priority class the process runs with now, e.g. "above_normal". Windows only.
+pub fn current() -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentProcess, GetPriorityClass, ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS,
+            HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS, REALTIME_PRIORITY_CLASS,
+        };
+
+        let class = unsafe { GetPriorityClass(GetCurrentProcess()) };
+        let name = match class {
+            0 => return Err(format!("GetPriorityClass failed: {}", std::io::Error::last_os_error())),
+            IDLE_PRIORITY_CLASS => "idle",
+            BELOW_NORMAL_PRIORITY_CLASS => "below_normal",
+            NORMAL_PRIORITY_CLASS => "normal",
+            ABOVE_NORMAL_PRIORITY_CLASS => "above_normal",
+            HIGH_PRIORITY_CLASS => "high",
+            REALTIME_PRIORITY_CLASS => "realtime",
+            other => return Ok(format!("unknown ({:#x})", other)),
+        };
+        Ok(name.to_string())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err("Process priority can only be read on Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_priorities_are_accepted() {
+        assert_eq!(Priority::from_setting("normal"), Some(Priority::Normal));
+        assert_eq!(Priority::from_setting("above_normal"), Some(Priority::AboveNormal));
+        // Higher classes would let the overlay starve the game it is drawn over
+        assert_eq!(Priority::from_setting("high"), None);
+        assert_eq!(Priority::from_setting("realtime"), None);
+    }
+}
@@ -0,0 +1,32 @@
+// Verbose logging of what the client sent and what the frontend was told, for bug reports
+// about garbled text or wrong states. Off by default because it logs conversation text;
+// switched at runtime with set_raw_logging and never saved.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Logs bytes from the client as they arrived, before any parsing. `source` is e.g. "sse".
+pub fn received(source: &str, raw: &[u8]) {
+    if enabled() {
+        println!("[Rust] [raw] {} received: {:?}", source, String::from_utf8_lossy(raw));
+    }
+}
+
+/// Logs an event sent to the frontend with its payload.
+pub fn emitted(event: &str, payload: &impl serde::Serialize) {
+    if enabled() {
+        match serde_json::to_string(payload) {
+            Ok(json) => println!("[Rust] [raw] emit {}: {}", event, json),
+            Err(e) => println!("[Rust] [raw] emit {}: <unserializable: {}>", event, e),
+        }
+    }
+}
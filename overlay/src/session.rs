@@ -0,0 +1,104 @@
+// Recorded status payloads with their timing. A session recorded from the live client can be
+// replayed through process_status_data later, to reproduce a bug or as a regression fixture.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+// A recording left running stops growing here
+const MAX_RECORDED_PAYLOADS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPayload {
+    /// Milliseconds since the recording started
+    pub at_ms: u64,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub payloads: Vec<RecordedPayload>,
+}
+
+impl Session {
+    pub fn parse(contents: &str) -> Result<Session, String> {
+        let session: Session = serde_json::from_str(contents).map_err(|e| format!("Not a recorded session: {}", e))?;
+        if let Some(index) = session.payloads.windows(2).position(|pair| pair[1].at_ms < pair[0].at_ms) {
+            return Err(format!("payloads[{}] is earlier than the payload before it", index + 1));
+        }
+        Ok(session)
+    }
+}
+
+/// A recording in progress, written to `path` when it is stopped.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    path: PathBuf,
+    started_at: Instant,
+    session: Session,
+}
+
+impl Recorder {
+    pub fn new(path: PathBuf, started_at: Instant) -> Self {
+        Recorder { path, started_at, session: Session::default() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a payload received at `at`. Polling repeats the last payload every second; repeats
+    /// change nothing on screen and aren't recorded.
+    pub fn record(&mut self, payload: &serde_json::Value, at: Instant) {
+        let payloads = &mut self.session.payloads;
+        if payloads.len() >= MAX_RECORDED_PAYLOADS || payloads.last().is_some_and(|last| &last.payload == payload) {
+            return;
+        }
+        payloads.push(RecordedPayload {
+            at_ms: at.saturating_duration_since(self.started_at).as_millis() as u64,
+            payload: payload.clone(),
+        });
+    }
+
+    /// Writes the session to `path`, returning the number of payloads.
+    pub fn save(&self) -> Result<usize, String> {
+        let contents = serde_json::to_string_pretty(&self.session).map_err(|e| e.to_string())?;
+        fs::write(&self.path, contents).map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        Ok(self.session.payloads.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn recording_keeps_changes_with_relative_times() {
+        let path = std::env::temp_dir().join(format!("gaja-overlay-session-{}.json", std::process::id()));
+        let start = Instant::now();
+        let mut recorder = Recorder::new(path.clone(), start);
+        let listening = json!({"state": "listening"});
+        recorder.record(&listening, start + Duration::from_millis(20));
+        recorder.record(&listening, start + Duration::from_millis(1020));
+        recorder.record(&json!({"state": "response", "text": "Cześć"}), start + Duration::from_millis(2500));
+        assert_eq!(recorder.save().unwrap(), 2);
+
+        let session = Session::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        let times: Vec<u64> = session.payloads.iter().map(|recorded| recorded.at_ms).collect();
+        assert_eq!(times, [20, 2500]);
+        assert_eq!(session.payloads[1].payload["text"], "Cześć");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn payloads_out_of_order_are_rejected() {
+        let contents = r#"{"payloads": [{"at_ms": 500, "payload": {}}, {"at_ms": 100, "payload": {}}]}"#;
+        assert!(Session::parse(contents).unwrap_err().contains("payloads[1]"));
+        assert!(Session::parse(r#"{"payloads": []}"#).is_ok());
+        assert!(Session::parse("[]").is_err());
+    }
+}
@@ -0,0 +1,1095 @@
+// Persistent overlay settings (overlay_settings.json in GAJA_CONFIG_DIR, the OS config
+// directory or next to the executable, whichever is writable first)
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+const SETTINGS_FILE_NAME: &str = "overlay_settings.json";
+// Subdirectory of the OS config directory (e.g. %APPDATA%\Gaja)
+const CONFIG_DIR_NAME: &str = "Gaja";
+// Below this the text is unreadable, above it a short response no longer fits the overlay
+const TEXT_SCALE_MIN: f64 = 0.5;
+const TEXT_SCALE_MAX: f64 = 3.0;
+// Re-stacking the window more often than this only costs CPU
+const REASSERT_TOPMOST_MIN_MS: u64 = 100;
+// Polling faster than this only loads the client
+const POLL_MIN_MS: u64 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    /// "fullscreen" or a corner: "top-left", "top-right", "bottom-left", "bottom-right"
+    pub position: String,
+    /// Minimum time a notification stays on screen before the next queued one replaces it
+    pub notification_min_duration_ms: u64,
+    /// Minimum time the wake word acknowledgement stays on screen, 0 to follow the client exactly
+    pub ack_duration_ms: u64,
+    /// Overlay content opacity, 0.0-1.0
+    pub opacity: f64,
+    /// Overlay text size multiplier, 0.5-3.0
+    pub text_scale: f64,
+    /// Response text shorter than this many characters doesn't bring up the overlay
+    pub min_display_chars: usize,
+    /// The same for text sent without a `state`, which may be a stray transcription
+    pub min_raw_text_chars: usize,
+    /// "top" (above all windows) or "bottom" (behind normal windows)
+    pub z_order: String,
+    /// With "top", puts the overlay back on top this often, for games that push it down; 0 is off
+    pub reassert_topmost_ms: u64,
+    /// "full" (status and response text) or "compact" (a small status dot in a corner)
+    pub display_mode: String,
+    /// Set when the user hides the overlay (tray or toggle); status updates don't show it again
+    pub user_disabled: bool,
+    /// Let listening/speaking show the overlay even while `user_disabled` is set
+    pub show_live_when_disabled: bool,
+    /// Let listening/speaking show the overlay while it is snoozed (snooze_overlay)
+    pub live_during_snooze: bool,
+    /// Clicks pass through the overlay to the windows below (except in interactive mode)
+    pub click_through: bool,
+    /// Show desktop notifications for "notify" actions from the client
+    pub notifications_enabled: bool,
+    /// Show the overlay on the monitor under the mouse cursor instead of the primary one (Windows)
+    pub follow_active_monitor: bool,
+    /// With "fullscreen", size the window to the rendered content instead of the whole monitor
+    pub fit_to_content: bool,
+    /// Moves the overlay content right/down from where it is normally drawn, in CSS pixels
+    pub content_offset_x: i32,
+    pub content_offset_y: i32,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        OverlaySettings {
+            position: "fullscreen".to_string(),
+            notification_min_duration_ms: 5000,
+            ack_duration_ms: 1500,
+            opacity: 1.0,
+            text_scale: 1.0,
+            min_display_chars: 1,
+            min_raw_text_chars: 1,
+            z_order: "top".to_string(),
+            reassert_topmost_ms: 0,
+            display_mode: "full".to_string(),
+            user_disabled: false,
+            show_live_when_disabled: false,
+            live_during_snooze: true,
+            click_through: true,
+            notifications_enabled: true,
+            follow_active_monitor: false,
+            fit_to_content: false,
+            content_offset_x: 0,
+            content_offset_y: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceSettings {
+    pub wake_word: String,
+    /// Wake word detection sensitivity, 0.0-1.0
+    pub sensitivity: f64,
+    /// Language code ("pl-PL", "en", ...) used by the assistant and for overlay labels
+    pub language: String,
+}
+
+// Same defaults as the client's settings manager
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        VoiceSettings {
+            wake_word: "gaja".to_string(),
+            sensitivity: 0.6,
+            language: "pl-PL".to_string(),
+        }
+    }
+}
+
+/// Audio devices for the client, as device ids from `get_audio_devices`; empty for the system default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub input_device: String,
+    pub output_device: String,
+}
+
+impl AudioSettings {
+    /// Checks both devices with `resolve` (device id, is_input), listing every failure.
+    /// Empty ids are the system default and aren't looked up.
+    pub fn check_devices(&self, resolve: impl Fn(&str, bool) -> Result<(), String>) -> Result<(), String> {
+        let errors: Vec<String> = [(&self.input_device, true), (&self.output_device, false)]
+            .into_iter()
+            .filter(|(id, _)| !id.is_empty())
+            .filter_map(|(id, is_input)| resolve(id, is_input).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyBriefingSettings {
+    pub enabled: bool,
+    /// Also request a briefing once after the overlay first connects
+    pub startup_briefing: bool,
+    /// Local time of day, "HH:MM"
+    pub briefing_time: String,
+    /// Sent to the client with the briefing request (weather etc.), "City,CC"
+    pub location: String,
+    /// While `location` is empty, look up an approximate location from the public IP.
+    /// Off by default because it sends a request to an outside service.
+    pub detect_location: bool,
+}
+
+impl Default for DailyBriefingSettings {
+    fn default() -> Self {
+        DailyBriefingSettings {
+            enabled: false,
+            startup_briefing: false,
+            briefing_time: "08:00".to_string(),
+            location: String::new(),
+            detect_location: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsSettings {
+    /// Serve Prometheus metrics on http://bind_address:port/metrics (needs a restart)
+    pub enabled: bool,
+    pub port: u16,
+    /// Loopback by default; another address exposes the endpoint on the network
+    pub bind_address: String,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        MetricsSettings {
+            enabled: false,
+            port: 9464,
+            bind_address: "127.0.0.1".to_string(),
+        }
+    }
+}
+
+/// Status request intervals in polling mode: fast while the assistant is active, stepping back
+/// to the idle interval once it has been quiet for `idle_after_ms`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollingSettings {
+    pub fast_poll_ms: u64,
+    pub idle_poll_ms: u64,
+    pub idle_after_ms: u64,
+}
+
+impl Default for PollingSettings {
+    fn default() -> Self {
+        PollingSettings {
+            fast_poll_ms: 250,
+            idle_poll_ms: 1000,
+            idle_after_ms: 10_000,
+        }
+    }
+}
+
+/// Overlay and settings window colors, as "#RRGGBB" or "#RGB"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// The animated ball
+    pub accent_color: String,
+    /// Behind the response text
+    pub background_color: String,
+    pub text_color: String,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        ThemeSettings {
+            accent_color: "#4CAF50".to_string(),
+            background_color: "#FFFFFF".to_string(),
+            text_color: "#2E2E2E".to_string(),
+        }
+    }
+}
+
+fn is_hex_color(value: &str) -> bool {
+    value
+        .strip_prefix('#')
+        .is_some_and(|digits| matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// A time span in which the overlay doesn't come up on its own (Settings.do_not_disturb)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DndWindow {
+    /// Local time, "HH:MM"
+    pub start: String,
+    /// "HH:MM"; earlier than `start` means the window ends the next day
+    pub end: String,
+    /// Days the window starts on ("mon".."sun"), empty for every day
+    pub days: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub overlay: OverlaySettings,
+    pub voice: VoiceSettings,
+    pub audio: AudioSettings,
+    pub daily_briefing: DailyBriefingSettings,
+    /// While one of these is active, only listening and speaking show the overlay
+    pub do_not_disturb: Vec<DndWindow>,
+    pub metrics: MetricsSettings,
+    pub theme: ThemeSettings,
+    pub polling: PollingSettings,
+    /// Client host including the scheme, e.g. "http://localhost" or "https://192.168.1.20"
+    pub host: String,
+    /// Client ports tried, in order, when looking for the assistant
+    pub ports: Vec<u16>,
+    /// Prefix for every client endpoint, e.g. "/gaja" behind a reverse proxy; empty for none
+    pub base_path: String,
+    /// Client status endpoint, polled and used to detect the client
+    pub status_path: String,
+    /// Client server-sent events endpoint
+    pub stream_path: String,
+    /// "auto" (SSE, falling back to polling), or "sse", "polling" or "websocket" to use only that one
+    pub transport: String,
+    /// Server-sent events endpoint of the server log, shown by the settings window; empty for none
+    pub log_stream_url: String,
+    /// Accept self-signed or otherwise invalid TLS certificates (https hosts only, needs a restart)
+    pub allow_invalid_certs: bool,
+    /// Enables testing commands such as set_debug_state in release builds
+    pub debug_commands: bool,
+    /// "normal" or "above_normal" (Windows), so updates stay timely while a game is running
+    pub process_priority: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            overlay: OverlaySettings::default(),
+            voice: VoiceSettings::default(),
+            audio: AudioSettings::default(),
+            daily_briefing: DailyBriefingSettings::default(),
+            do_not_disturb: Vec::new(),
+            metrics: MetricsSettings::default(),
+            theme: ThemeSettings::default(),
+            polling: PollingSettings::default(),
+            host: "http://localhost".to_string(),
+            ports: vec![5000, 5001],
+            base_path: String::new(),
+            status_path: "/api/status".to_string(),
+            stream_path: "/status/stream".to_string(),
+            transport: "auto".to_string(),
+            log_stream_url: String::new(),
+            allow_invalid_certs: false,
+            debug_commands: false,
+            process_priority: "normal".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// Ports to probe in order: GAJA_PORT (if set and valid) first, then the configured list.
+    pub fn candidate_ports(&self) -> Vec<u16> {
+        self.candidate_ports_with(std::env::var("GAJA_PORT").ok().as_deref())
+    }
+
+    fn candidate_ports_with(&self, gaja_port: Option<&str>) -> Vec<u16> {
+        let mut ports = Vec::new();
+        if let Some(value) = gaja_port {
+            match value.trim().parse::<u16>() {
+                Ok(port) if port != 0 => ports.push(port),
+                _ => eprintln!("[Rust] Ignoring invalid GAJA_PORT value '{}'", value),
+            }
+        }
+        for &port in &self.ports {
+            if port == 0 {
+                eprintln!("[Rust] Ignoring invalid port 0 in settings");
+            } else if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+        if ports.is_empty() {
+            ports = Settings::default().ports;
+        }
+        ports
+    }
+
+    /// Base URLs to try for the client on `port`, in order, including `base_path`. Hosts without a scheme use http.
+    /// "localhost" is tried as 127.0.0.1, ::1 and by name, because it may resolve to an
+    /// address family the client doesn't listen on. IPv6 addresses may be given without brackets.
+    pub fn client_base_urls(&self, port: u16) -> Vec<String> {
+        let host = self.host.trim().trim_end_matches('/');
+        let (scheme, name) = host.split_once("://").unwrap_or(("http", host));
+        let name = name.trim_start_matches('[').trim_end_matches(']');
+        let hosts = if name.eq_ignore_ascii_case("localhost") {
+            vec!["127.0.0.1".to_string(), "[::1]".to_string(), name.to_string()]
+        } else if name.parse::<std::net::Ipv6Addr>().is_ok() {
+            vec![format!("[{}]", name)]
+        } else {
+            vec![name.to_string()]
+        };
+        let base_path = self.base_path.trim().trim_end_matches('/');
+        hosts
+            .into_iter()
+            .map(|host| format!("{}://{}:{}{}", scheme, host, port, base_path))
+            .collect()
+    }
+
+    /// A copy with `voice` in place of the current voice settings, or the validation error.
+    pub fn with_voice(&self, voice: VoiceSettings) -> Result<Settings, String> {
+        let updated = Settings { voice, ..self.clone() };
+        updated.validate()?;
+        Ok(updated)
+    }
+
+    /// Checks value ranges, listing every offending field in the error.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        let mut check_unit_range = |field: &str, value: f64| {
+            // Written this way so NaN is rejected too
+            if !(0.0..=1.0).contains(&value) {
+                errors.push(format!("{} must be between 0 and 1 (got {})", field, value));
+            }
+        };
+        check_unit_range("voice.sensitivity", self.voice.sensitivity);
+        check_unit_range("overlay.opacity", self.overlay.opacity);
+
+        if !(TEXT_SCALE_MIN..=TEXT_SCALE_MAX).contains(&self.overlay.text_scale) {
+            errors.push(format!(
+                "overlay.text_scale must be between {} and {} (got {})",
+                TEXT_SCALE_MIN, TEXT_SCALE_MAX, self.overlay.text_scale
+            ));
+        }
+
+        if self.overlay.reassert_topmost_ms != 0 && self.overlay.reassert_topmost_ms < REASSERT_TOPMOST_MIN_MS {
+            errors.push(format!(
+                "overlay.reassert_topmost_ms must be 0 (off) or at least {} (got {})",
+                REASSERT_TOPMOST_MIN_MS, self.overlay.reassert_topmost_ms
+            ));
+        }
+
+        let mut check_choice = |field: &str, value: &str, allowed: &[&str]| {
+            if !allowed.contains(&value) {
+                errors.push(format!("{} must be one of {:?} (got \"{}\")", field, allowed, value));
+            }
+        };
+        check_choice("overlay.z_order", &self.overlay.z_order, &["top", "bottom"]);
+        check_choice("overlay.display_mode", &self.overlay.display_mode, &["full", "compact"]);
+        check_choice("process_priority", &self.process_priority, &["normal", "above_normal"]);
+        check_choice("transport", &self.transport, &["auto", "sse", "polling", "websocket"]);
+
+        for (field, value) in [
+            ("theme.accent_color", &self.theme.accent_color),
+            ("theme.background_color", &self.theme.background_color),
+            ("theme.text_color", &self.theme.text_color),
+        ] {
+            if !is_hex_color(value) {
+                errors.push(format!("{} must be a hex color like \"#4CAF50\" (got \"{}\")", field, value));
+            }
+        }
+
+        let mut check_path = |field: &str, value: &str, optional: bool| {
+            if !(value.starts_with('/') || (optional && value.is_empty())) {
+                errors.push(format!("{} must start with \"/\" (got \"{}\")", field, value));
+            }
+        };
+        check_path("base_path", &self.base_path, true);
+        check_path("status_path", &self.status_path, false);
+        check_path("stream_path", &self.stream_path, false);
+
+        if !self.log_stream_url.is_empty() {
+            match reqwest::Url::parse(&self.log_stream_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => errors.push(format!("log_stream_url must be an http(s) URL (got \"{}\")", self.log_stream_url)),
+            }
+        }
+
+        if let Err(e) = crate::briefing::parse_briefing_time(&self.daily_briefing.briefing_time) {
+            errors.push(format!("daily_briefing.briefing_time: {}", e));
+        }
+        for (index, window) in self.do_not_disturb.iter().enumerate() {
+            if let Err(e) = crate::dnd::check_window(window) {
+                errors.push(format!("do_not_disturb[{}]: {}", index, e));
+            }
+        }
+
+        if self.metrics.bind_address.parse::<std::net::IpAddr>().is_err() {
+            errors.push(format!(
+                "metrics.bind_address must be an IP address (got \"{}\")",
+                self.metrics.bind_address
+            ));
+        }
+        if self.metrics.port == 0 {
+            errors.push("metrics.port must not be 0".to_string());
+        }
+
+        if self.polling.fast_poll_ms < POLL_MIN_MS {
+            errors.push(format!(
+                "polling.fast_poll_ms must be at least {} (got {})",
+                POLL_MIN_MS, self.polling.fast_poll_ms
+            ));
+        }
+        if self.polling.idle_poll_ms < self.polling.fast_poll_ms {
+            errors.push(format!(
+                "polling.idle_poll_ms must not be below polling.fast_poll_ms (got {} and {})",
+                self.polling.idle_poll_ms, self.polling.fast_poll_ms
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Invalid settings: {}", errors.join("; ")))
+        }
+    }
+}
+
+pub type SharedSettings = Arc<Mutex<Settings>>;
+
+/// Where an effective configuration value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+}
+
+/// One entry of `get_effective_config`: a setting as a dotted key such as "overlay.position".
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigValue {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+}
+
+// Adds every leaf of `value` (arrays count as one value) under `prefix`
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut Vec<(String, serde_json::Value)>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields {
+                let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+                flatten(&key, field, out);
+            }
+        }
+        leaf => out.push((prefix.to_string(), leaf.clone())),
+    }
+}
+
+/// Every setting in effect, and whether it is the default, comes from the settings file
+/// (`file`, the parsed file if it was valid) or is overridden by the environment (`gaja_port`,
+/// the value of GAJA_PORT). Values changed since the last write count as from the file.
+pub fn effective_config(settings: &Settings, file: Option<&serde_json::Value>, gaja_port: Option<&str>) -> Vec<ConfigValue> {
+    let to_leaves = |settings: &Settings| {
+        let mut leaves = Vec::new();
+        flatten("", &serde_json::to_value(settings).unwrap_or_default(), &mut leaves);
+        leaves
+    };
+    let defaults: std::collections::HashMap<_, _> = to_leaves(&Settings::default()).into_iter().collect();
+    let ports_from_env = gaja_port.and_then(|value| value.trim().parse::<u16>().ok()).is_some_and(|port| port != 0);
+
+    to_leaves(settings)
+        .into_iter()
+        .map(|(key, value)| {
+            if key == "ports" && ports_from_env {
+                let ports = settings.candidate_ports_with(gaja_port);
+                return ConfigValue { key, value: serde_json::json!(ports), source: ConfigSource::Env };
+            }
+            let pointer = format!("/{}", key.replace('.', "/"));
+            let in_file = file.is_some_and(|file| file.pointer(&pointer).is_some());
+            let source = if in_file || defaults.get(&key) != Some(&value) {
+                ConfigSource::File
+            } else {
+                ConfigSource::Default
+            };
+            ConfigValue { key, value, source }
+        })
+        .collect()
+}
+
+// Serializes settings file writes so two saves can't interleave on the temp file
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// Chosen once per run so loads and saves always agree on the location
+static SETTINGS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe().ok()?.parent().map(Path::to_path_buf)
+}
+
+// Creates `dir` if needed and checks that a file can be written in it
+fn ensure_writable_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let probe = dir.join(".overlay_write_test");
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
+/// Settings file location: GAJA_CONFIG_DIR, then the OS config directory, then the
+/// executable's directory (which is read-only when installed under Program Files).
+pub fn get_settings_path() -> Result<PathBuf, String> {
+    if let Some(dir) = SETTINGS_DIR.get() {
+        return Ok(dir.join(SETTINGS_FILE_NAME));
+    }
+
+    let mut candidates = Vec::new();
+    if let Some(dir) = std::env::var_os("GAJA_CONFIG_DIR").filter(|d| !d.is_empty()) {
+        candidates.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = dirs::config_dir() {
+        candidates.push(dir.join(CONFIG_DIR_NAME));
+    }
+    if let Some(dir) = exe_dir() {
+        candidates.push(dir);
+    }
+
+    let mut errors = Vec::new();
+    for dir in candidates {
+        match ensure_writable_dir(&dir) {
+            Ok(()) => {
+                let dir = SETTINGS_DIR.get_or_init(|| dir);
+                println!("[Rust] Using settings directory {}", dir.display());
+                return Ok(dir.join(SETTINGS_FILE_NAME));
+            }
+            Err(e) => errors.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+    Err(format!("No writable settings directory found ({})", errors.join("; ")))
+}
+
+/// Self-test: the settings directory can be written. Returns the directory.
+pub fn check_settings_dir() -> Result<String, String> {
+    let path = get_settings_path()?;
+    let dir = path.parent().ok_or("Settings path has no directory")?;
+    ensure_writable_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(dir.display().to_string())
+}
+
+/// Self-test: the settings file, if there is one, parses and can be opened for writing.
+pub fn check_settings_file() -> Result<String, String> {
+    let path = get_settings_path()?;
+    if !path.exists() {
+        return Ok(format!("{} doesn't exist yet, defaults are in use", path.display()));
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+    serde_json::from_str::<Settings>(&contents).map_err(|e| format!("{} is invalid: {}", path.display(), e))?;
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{} is not writable: {}", path.display(), e))?;
+    Ok(format!("{} is readable and writable", path.display()))
+}
+
+/// `get_effective_config`: the settings in use with their sources, headed by the settings file
+/// location (chosen from GAJA_CONFIG_DIR or the default directories).
+#[tauri::command]
+pub fn get_effective_config(settings: tauri::State<'_, SharedSettings>) -> Result<Vec<ConfigValue>, String> {
+    let path = get_settings_path()?;
+    let config_dir = std::env::var_os("GAJA_CONFIG_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    let path_source = if config_dir.is_some_and(|dir| path.parent() == Some(dir.as_path())) {
+        ConfigSource::Env
+    } else {
+        ConfigSource::Default
+    };
+    // A file that doesn't parse as settings isn't used, so nothing comes from it
+    let file = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .filter(|value| serde_json::from_value::<Settings>(value.clone()).is_ok());
+
+    let mut config = vec![ConfigValue {
+        key: "settings_path".to_string(),
+        value: serde_json::json!(path.display().to_string()),
+        source: path_source,
+    }];
+    let settings = settings.lock().unwrap().clone();
+    config.extend(effective_config(&settings, file.as_ref(), std::env::var("GAJA_PORT").ok().as_deref()));
+    Ok(config)
+}
+
+/// Reads settings from disk, falling back to defaults if the file is missing or invalid.
+pub fn load_settings_from_disk() -> Settings {
+    match get_settings_path() {
+        Ok(path) => {
+            // Older versions always kept the file next to the executable
+            let legacy_path = exe_dir().map(|dir| dir.join(SETTINGS_FILE_NAME));
+            match legacy_path {
+                Some(legacy) if !path.exists() && legacy != path && legacy.exists() => {
+                    println!("[Rust] Loading settings from previous location {}", legacy.display());
+                    load_settings_from(&legacy)
+                }
+                _ => load_settings_from(&path),
+            }
+        }
+        Err(e) => {
+            eprintln!("[Rust] {}, using default settings", e);
+            Settings::default()
+        }
+    }
+}
+
+fn load_settings_from(path: &Path) -> Settings {
+    match fs::read_to_string(path) {
+        Ok(contents) => match serde_json::from_str::<Settings>(&contents) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[Rust] Failed to parse {}: {}, using default settings", path.display(), e);
+                Settings::default()
+            }
+        },
+        Err(_) => {
+            println!("[Rust] No settings file at {}, using defaults", path.display());
+            Settings::default()
+        }
+    }
+}
+
+// Settings changed by controls that may fire many times in a row (a slider, nudging the
+// content offset, toggling from the tray) are written at most once per this long
+const FLUSH_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+struct PendingWrite {
+    settings: Option<Settings>,
+    last_flush: Option<chrono::DateTime<chrono::Local>>,
+}
+
+/// The only way settings get to disk. Explicit saves are written at once so their errors
+/// reach the caller; `schedule` batches the rest. The lock is held across every write, so a
+/// batched write can't overwrite a newer one.
+#[derive(Debug, Default)]
+pub struct SettingsWriter {
+    pending: Mutex<PendingWrite>,
+    wake: tokio::sync::Notify,
+    // None for the settings file; tests write elsewhere
+    path: Option<PathBuf>,
+}
+
+impl SettingsWriter {
+    /// Writes `settings` now, replacing anything scheduled.
+    pub fn write_now(&self, settings: &Settings) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.settings = None;
+        self.write(&mut pending, settings)
+    }
+
+    /// Writes `settings` within FLUSH_DELAY, unless newer settings come first.
+    pub fn schedule(&self, settings: Settings) {
+        self.pending.lock().unwrap().settings = Some(settings);
+        self.wake.notify_one();
+    }
+
+    /// Writes scheduled settings now, e.g. on shutdown.
+    pub fn flush(&self) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(settings) = pending.settings.take() {
+            if let Err(e) = self.write(&mut pending, &settings) {
+                eprintln!("[Rust] Failed to save settings: {}", e);
+            }
+        }
+    }
+
+    // Drops scheduled settings, for when the file is about to be replaced by other means
+    fn discard_pending(&self) -> std::sync::MutexGuard<'_, PendingWrite> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.settings = None;
+        pending
+    }
+
+    pub fn last_flush(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        self.pending.lock().unwrap().last_flush
+    }
+
+    /// Flushes FLUSH_DELAY after the first of a run of scheduled changes. Runs for the
+    /// lifetime of the app.
+    pub async fn run(&self) {
+        loop {
+            self.wake.notified().await;
+            tokio::time::sleep(FLUSH_DELAY).await;
+            self.flush();
+        }
+    }
+
+    fn write(&self, pending: &mut PendingWrite, settings: &Settings) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => get_settings_path()?,
+        };
+        write_settings_to(&path, settings)?;
+        pending.last_flush = Some(chrono::Local::now());
+        Ok(())
+    }
+}
+
+/// Writes to a temp file next to `path` and renames it over `path`, so a crash mid-write
+/// leaves either the old or the new file, never a truncated one.
+fn write_settings_to(path: &Path, settings: &Settings) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    let tmp_path = path.with_extension("json.tmp");
+
+    let _write_guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let write_tmp = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    };
+    write_tmp().map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to replace {}: {}", path.display(), e)
+    })
+}
+
+// Moves the settings file to overlay_settings.json.bak, returns the backup path if there was a file
+fn backup_settings_file(path: &Path) -> Result<Option<PathBuf>, String> {
+    let _write_guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if !path.exists() {
+        return Ok(None);
+    }
+    let backup_path = path.with_extension("json.bak");
+    fs::rename(path, &backup_path)
+        .map_err(|e| format!("Failed to move {} to {}: {}", path.display(), backup_path.display(), e))?;
+    Ok(Some(backup_path))
+}
+
+#[tauri::command]
+pub fn load_settings(settings: tauri::State<'_, SharedSettings>) -> Result<Settings, String> {
+    Ok(settings.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub fn save_settings(
+    new_settings: Settings,
+    settings: tauri::State<'_, SharedSettings>,
+    writer: tauri::State<'_, SettingsWriter>,
+) -> Result<(), String> {
+    new_settings.validate()?;
+    // Held across the write so the file and the in-memory copy end up on the same save
+    let mut settings_guard = settings.lock().unwrap();
+    writer.write_now(&new_settings)?;
+    *settings_guard = new_settings;
+    println!("[Rust] Settings saved");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn export_settings(path: String, settings: tauri::State<'_, SharedSettings>) -> Result<(), String> {
+    let settings_guard = settings.lock().unwrap();
+    write_settings_to(Path::new(&path), &settings_guard)?;
+    println!("[Rust] Settings exported to {}", path);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_settings(
+    path: String,
+    settings: tauri::State<'_, SharedSettings>,
+    writer: tauri::State<'_, SettingsWriter>,
+) -> Result<Settings, String> {
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let imported: Settings = serde_json::from_str(&contents)
+        .map_err(|e| format!("{} is not a valid settings file: {}", path, e))?;
+    imported.validate()?;
+
+    let mut settings_guard = settings.lock().unwrap();
+    writer.write_now(&imported)?;
+    *settings_guard = imported.clone();
+    println!("[Rust] Settings imported from {}", path);
+    Ok(imported)
+}
+
+/// Restores defaults. The previous file is kept as overlay_settings.json.bak.
+#[tauri::command]
+pub fn reset_settings(
+    settings: tauri::State<'_, SharedSettings>,
+    writer: tauri::State<'_, SettingsWriter>,
+) -> Result<Settings, String> {
+    let mut settings_guard = settings.lock().unwrap();
+    // A scheduled write would bring the old settings back after the file is moved away
+    let _pending = writer.discard_pending();
+    if let Some(backup_path) = backup_settings_file(&get_settings_path()?)? {
+        println!("[Rust] Previous settings moved to {}", backup_path.display());
+    }
+    *settings_guard = Settings::default();
+    println!("[Rust] Settings reset to defaults");
+    Ok(settings_guard.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_values(sensitivity: f64, opacity: f64) -> Settings {
+        let mut settings = Settings::default();
+        settings.voice.sensitivity = sensitivity;
+        settings.overlay.opacity = opacity;
+        settings
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn range_boundaries_are_accepted() {
+        assert!(with_values(0.0, 0.0).validate().is_ok());
+        assert!(with_values(1.0, 1.0).validate().is_ok());
+    }
+
+    #[test]
+    fn out_of_range_sensitivity_is_rejected() {
+        let err = with_values(5.0, 1.0).validate().unwrap_err();
+        assert!(err.contains("voice.sensitivity"));
+        assert!(!err.contains("overlay.opacity"));
+        assert!(with_values(1.0001, 1.0).validate().is_err());
+        assert!(with_values(-0.1, 1.0).validate().is_err());
+    }
+
+    #[test]
+    fn text_scale_range_is_enforced() {
+        let with_scale = |text_scale| Settings {
+            overlay: OverlaySettings { text_scale, ..OverlaySettings::default() },
+            ..Settings::default()
+        };
+        assert!(with_scale(0.5).validate().is_ok());
+        assert!(with_scale(3.0).validate().is_ok());
+        assert!(with_scale(0.4).validate().unwrap_err().contains("overlay.text_scale"));
+        assert!(with_scale(3.5).validate().is_err());
+        assert!(with_scale(f64::NAN).validate().is_err());
+    }
+
+    #[test]
+    fn all_offending_fields_are_listed() {
+        let err = with_values(5.0, -1.0).validate().unwrap_err();
+        assert!(err.contains("voice.sensitivity"));
+        assert!(err.contains("overlay.opacity"));
+    }
+
+    #[test]
+    fn scheduled_writes_are_batched_and_never_overwrite_newer_ones() {
+        let dir = std::env::temp_dir().join(format!("gaja-overlay-writer-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SETTINGS_FILE_NAME);
+        let writer = SettingsWriter { path: Some(path.clone()), ..SettingsWriter::default() };
+        let with_position = |position: &str| Settings {
+            overlay: OverlaySettings { position: position.to_string(), ..OverlaySettings::default() },
+            ..Settings::default()
+        };
+
+        writer.schedule(with_position("top-left"));
+        writer.schedule(with_position("top-right"));
+        assert!(!path.exists());
+        assert!(writer.last_flush().is_none());
+        writer.flush();
+        assert_eq!(load_settings_from(&path).overlay.position, "top-right");
+        assert!(writer.last_flush().is_some());
+
+        // An explicit save replaces what was scheduled before it
+        writer.schedule(with_position("bottom-left"));
+        writer.write_now(&with_position("bottom-right")).unwrap();
+        writer.flush();
+        assert_eq!(load_settings_from(&path).overlay.position, "bottom-right");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn atomic_write_replaces_file_after_interrupted_write() {
+        let dir = std::env::temp_dir().join(format!("gaja-overlay-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SETTINGS_FILE_NAME);
+
+        let mut old = Settings::default();
+        old.overlay.position = "top-left".to_string();
+        write_settings_to(&path, &old).unwrap();
+
+        // A write killed halfway leaves a truncated temp file; the real file is untouched
+        fs::write(path.with_extension("json.tmp"), r#"{"overlay": {"posi"#).unwrap();
+        assert_eq!(load_settings_from(&path).overlay.position, "top-left");
+
+        let mut new = Settings::default();
+        new.overlay.position = "bottom-right".to_string();
+        write_settings_to(&path, &new).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let loaded: Settings = serde_json::from_str(&contents).unwrap();
+        assert_eq!(loaded.overlay.position, "bottom-right");
+        assert!(!path.with_extension("json.tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_z_order_is_rejected() {
+        let mut settings = Settings::default();
+        settings.overlay.z_order = "middle".to_string();
+        assert!(settings.validate().unwrap_err().contains("overlay.z_order"));
+        settings.overlay.z_order = "bottom".to_string();
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn theme_colors_must_be_hex() {
+        let mut settings = Settings::default();
+        settings.theme.accent_color = "#abc".to_string();
+        assert!(settings.validate().is_ok());
+        for invalid in ["4CAF50", "#4CAF5", "#GGGGGG", "green", "#4CAF50FF"] {
+            settings.theme.text_color = invalid.to_string();
+            assert!(settings.validate().unwrap_err().contains("theme.text_color"), "{} was accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn topmost_reassert_is_off_or_not_too_frequent() {
+        let mut settings = Settings::default();
+        settings.overlay.reassert_topmost_ms = 10;
+        assert!(settings.validate().unwrap_err().contains("overlay.reassert_topmost_ms"));
+        settings.overlay.reassert_topmost_ms = 1000;
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn briefing_time_must_be_hh_mm() {
+        let mut settings = Settings::default();
+        settings.daily_briefing.briefing_time = "8 rano".to_string();
+        let error = settings.validate().unwrap_err();
+        assert!(error.contains("daily_briefing.briefing_time"), "{}", error);
+        settings.daily_briefing.briefing_time = "24:00".to_string();
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn nan_is_rejected() {
+        assert!(with_values(f64::NAN, 1.0).validate().is_err());
+        assert!(with_values(0.5, f64::NAN).validate().is_err());
+    }
+
+    #[test]
+    fn localhost_is_tried_on_both_address_families() {
+        let mut settings = Settings::default();
+        assert_eq!(
+            settings.client_base_urls(5000),
+            ["http://127.0.0.1:5000", "http://[::1]:5000", "http://localhost:5000"]
+        );
+
+        settings.host = "https://::1/".to_string();
+        assert_eq!(settings.client_base_urls(5001), ["https://[::1]:5001"]);
+        settings.host = "192.168.1.20".to_string();
+        assert_eq!(settings.client_base_urls(5000), ["http://192.168.1.20:5000"]);
+    }
+
+    #[test]
+    fn base_path_is_part_of_every_base_url() {
+        let settings = Settings {
+            host: "https://example.com".to_string(),
+            base_path: "/gaja/".to_string(),
+            ..Settings::default()
+        };
+        assert_eq!(settings.client_base_urls(443), ["https://example.com:443/gaja"]);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn log_stream_url_must_be_empty_or_http() {
+        let mut settings = Settings::default();
+        assert!(settings.validate().is_ok());
+        settings.log_stream_url = "http://localhost:8001/api/v1/logs/stream".to_string();
+        assert!(settings.validate().is_ok());
+        settings.log_stream_url = "localhost:8001/logs".to_string();
+        assert!(settings.validate().unwrap_err().contains("log_stream_url"));
+    }
+
+    #[test]
+    fn endpoint_paths_must_start_with_a_slash() {
+        let settings = Settings {
+            base_path: "gaja".to_string(),
+            status_path: "api/status".to_string(),
+            stream_path: String::new(),
+            ..Settings::default()
+        };
+        let error = settings.validate().unwrap_err();
+        for field in ["status_path", "stream_path", "base_path"] {
+            assert!(error.contains(field), "{}", error);
+        }
+    }
+
+    #[test]
+    fn polling_intervals_are_checked() {
+        let mut settings = Settings::default();
+        settings.polling.fast_poll_ms = 10;
+        assert!(settings.validate().unwrap_err().contains("polling.fast_poll_ms"));
+        settings.polling.fast_poll_ms = 2000;
+        assert!(settings.validate().unwrap_err().contains("polling.idle_poll_ms"));
+        settings.polling.idle_poll_ms = 2000;
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn voice_update_is_validated() {
+        let settings = Settings::default();
+        for sensitivity in [5.0, -1.0, f64::NAN] {
+            let voice = VoiceSettings { sensitivity, ..VoiceSettings::default() };
+            assert!(settings.with_voice(voice).unwrap_err().contains("voice.sensitivity"));
+        }
+        let voice = VoiceSettings { sensitivity: 0.8, ..VoiceSettings::default() };
+        assert_eq!(settings.with_voice(voice).unwrap().voice.sensitivity, 0.8);
+    }
+
+    #[test]
+    fn empty_audio_device_means_the_default_and_is_not_looked_up() {
+        let resolve = |id: &str, is_input: bool| match (id, is_input) {
+            ("Speakers", false) | ("Mic", true) => Ok(()),
+            _ => Err(format!("device '{}' not found", id)),
+        };
+        let audio = AudioSettings { input_device: String::new(), output_device: "Speakers".to_string() };
+        assert_eq!(audio.check_devices(resolve), Ok(()));
+
+        let audio = AudioSettings { input_device: "Speakers".to_string(), output_device: "Gone".to_string() };
+        assert_eq!(audio.check_devices(resolve).unwrap_err(), "device 'Speakers' not found; device 'Gone' not found");
+    }
+
+    #[test]
+    fn metrics_bind_address_must_be_an_ip() {
+        let mut settings = Settings::default();
+        settings.metrics.bind_address = "::1".to_string();
+        assert!(settings.validate().is_ok());
+        settings.metrics.bind_address = "localhost".to_string();
+        assert!(settings.validate().unwrap_err().contains("metrics.bind_address"));
+    }
+
+    #[test]
+    fn effective_config_names_the_source_of_each_value() {
+        let file = serde_json::json!({ "overlay": { "position": "fullscreen" }, "ports": [5002] });
+        let settings = Settings {
+            ports: vec![5002],
+            overlay: OverlaySettings { z_order: "bottom".to_string(), ..OverlaySettings::default() },
+            ..Settings::default()
+        };
+        let source = |config: &[ConfigValue], key: &str| config.iter().find(|value| value.key == key).unwrap().source;
+
+        let config = effective_config(&settings, Some(&file), None);
+        // In the file, even though it's the default value
+        assert_eq!(source(&config, "overlay.position"), ConfigSource::File);
+        // Changed but not written yet
+        assert_eq!(source(&config, "overlay.z_order"), ConfigSource::File);
+        assert_eq!(source(&config, "host"), ConfigSource::Default);
+        assert_eq!(source(&config, "ports"), ConfigSource::File);
+
+        let config = effective_config(&settings, Some(&file), Some("5050"));
+        let ports = config.iter().find(|value| value.key == "ports").unwrap();
+        assert_eq!((ports.source, &ports.value), (ConfigSource::Env, &serde_json::json!([5050, 5002])));
+        // An invalid GAJA_PORT is ignored
+        assert_eq!(source(&effective_config(&settings, None, Some("abc")), "ports"), ConfigSource::File);
+    }
+}
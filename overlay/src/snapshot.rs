@@ -0,0 +1,179 @@
+// Everything in OverlayState that makes up what the overlay shows and does next, for test
+// harnesses that save the state and restore it later. Instants become durations relative to
+// the time of the snapshot. Left out: bookkeeping that only rate-limits or de-duplicates
+// (interactive_generation, last_monitor_follow, reconnect_requested, connection_changed, the
+// emit throttle), next_briefing_at, which the briefing scheduler recomputes, and
+// snoozed_until, whose end is run by the task snooze_overlay started.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::status::{self, Status};
+use crate::{tuning, OverlayState, ServerInfo, StatusHistoryEntry, STATUS_HISTORY_LEN};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FullState {
+    pub visible: bool,
+    status: String,
+    state: Status,
+    text: String,
+    partial: bool,
+    is_listening: bool,
+    is_speaking: bool,
+    wake_word_detected: bool,
+    // Time since last_activity_time; the overlay auto-hides after 30 seconds of it
+    idle_ms: u64,
+    current_monitor: Option<String>,
+    last_good_port: Option<u16>,
+    last_good_base_url: Option<String>,
+    connected: bool,
+    interactive: bool,
+    notification_queue: Vec<serde_json::Value>,
+    notification_shown_ms_ago: Option<u64>,
+    wake_ack_remaining_ms: Option<u64>,
+    held_after_ack: Option<serde_json::Value>,
+    dismissed_payload: Option<serde_json::Value>,
+    last_payload: Option<serde_json::Value>,
+    status_history: Vec<StatusHistoryEntry>,
+    voice_sync_pending: bool,
+    #[serde(default)]
+    audio_sync_pending: bool,
+    paused: bool,
+    user_disabled: bool,
+    last_error: Option<String>,
+    unexpected_response: Option<status::UnexpectedResponse>,
+    monitor_warning: Option<String>,
+    server_info: Option<ServerInfo>,
+    last_action: Option<serde_json::Value>,
+    sensitivity_preview: Option<f64>,
+    detected_location: Option<String>,
+    poll_task_restarts: u32,
+}
+
+fn ms_since(instant: Instant, now: Instant) -> u64 {
+    now.saturating_duration_since(instant).as_millis() as u64
+}
+
+// The instant `ms` before `now`, or `now` if the clock doesn't reach back that far
+fn ms_before(ms: u64, now: Instant) -> Instant {
+    now.checked_sub(Duration::from_millis(ms)).unwrap_or(now)
+}
+
+impl FullState {
+    pub fn capture(state: &OverlayState) -> FullState {
+        let now = Instant::now();
+        FullState {
+            visible: state.visible,
+            status: state.status.clone(),
+            state: state.state,
+            text: state.text.clone(),
+            partial: state.partial,
+            is_listening: state.is_listening,
+            is_speaking: state.is_speaking,
+            wake_word_detected: state.wake_word_detected,
+            idle_ms: ms_since(state.last_activity_time, now),
+            current_monitor: state.current_monitor.clone(),
+            last_good_port: state.last_good_port,
+            last_good_base_url: state.last_good_base_url.clone(),
+            connected: state.connected,
+            interactive: state.interactive,
+            notification_queue: state.notification_queue.iter().cloned().collect(),
+            notification_shown_ms_ago: state.notification_shown_at.map(|shown_at| ms_since(shown_at, now)),
+            wake_ack_remaining_ms: state.wake_ack_until.map(|until| until.saturating_duration_since(now).as_millis() as u64),
+            held_after_ack: state.held_after_ack.clone(),
+            dismissed_payload: state.dismissed_payload.clone(),
+            last_payload: state.last_payload.clone(),
+            status_history: state.status_history.iter().cloned().collect(),
+            voice_sync_pending: state.voice_sync_pending,
+            audio_sync_pending: state.audio_sync_pending,
+            paused: state.paused,
+            user_disabled: state.user_disabled,
+            last_error: state.last_error.clone(),
+            unexpected_response: state.unexpected_response.clone(),
+            monitor_warning: state.monitor_warning.clone(),
+            server_info: state.server_info.clone(),
+            last_action: state.last_action.clone(),
+            sensitivity_preview: state.tuning.preview(),
+            detected_location: state.detected_location.clone(),
+            poll_task_restarts: state.poll_task_restarts,
+        }
+    }
+
+    // Visibility is left to the caller, which has to show or hide the window to match
+    pub fn apply(self, state: &mut OverlayState) {
+        let now = Instant::now();
+        state.status = self.status;
+        state.state = self.state;
+        state.text = self.text;
+        state.partial = self.partial;
+        state.is_listening = self.is_listening;
+        state.is_speaking = self.is_speaking;
+        state.wake_word_detected = self.wake_word_detected;
+        state.last_activity_time = ms_before(self.idle_ms, now);
+        state.current_monitor = self.current_monitor;
+        state.last_good_port = self.last_good_port;
+        state.last_good_base_url = self.last_good_base_url;
+        state.connected = self.connected;
+        state.interactive = self.interactive;
+        state.notification_queue = self.notification_queue.into();
+        state.notification_shown_at = self.notification_shown_ms_ago.map(|ms| ms_before(ms, now));
+        state.wake_ack_until = self.wake_ack_remaining_ms.map(|ms| now + Duration::from_millis(ms));
+        state.held_after_ack = self.held_after_ack;
+        state.dismissed_payload = self.dismissed_payload;
+        state.last_payload = self.last_payload;
+        let skip = self.status_history.len().saturating_sub(STATUS_HISTORY_LEN);
+        state.status_history = self.status_history.into_iter().skip(skip).collect();
+        state.voice_sync_pending = self.voice_sync_pending;
+        state.audio_sync_pending = self.audio_sync_pending;
+        state.paused = self.paused;
+        state.user_disabled = self.user_disabled;
+        state.last_error = self.last_error;
+        state.unexpected_response = self.unexpected_response;
+        state.monitor_warning = self.monitor_warning;
+        state.server_info = self.server_info;
+        state.last_action = self.last_action;
+        state.tuning = tuning::Tuning::restored(self.sensitivity_preview);
+        state.detected_location = self.detected_location;
+        state.poll_task_restarts = self.poll_task_restarts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Time that may pass between the steps below on a slow machine
+    const SLACK_MS: u64 = 500;
+
+    fn assert_near(actual: Option<u64>, expected: u64) {
+        let actual = actual.unwrap();
+        assert!(actual.abs_diff(expected) <= SLACK_MS, "{} is not near {}", actual, expected);
+    }
+
+    #[test]
+    fn relative_times_survive_a_round_trip() {
+        let now = Instant::now();
+        let mut original = OverlayState::new();
+        original.text = "Cześć".to_string();
+        original.last_activity_time = ms_before(5000, now);
+        original.notification_shown_at = Some(ms_before(2000, now));
+        original.wake_ack_until = Some(now + Duration::from_millis(3000));
+        original.tuning.set_preview(0.7);
+
+        // As a test harness would keep it between get_full_state and restore_state
+        let json = serde_json::to_string(&FullState::capture(&original)).unwrap();
+        let mut restored = OverlayState::new();
+        serde_json::from_str::<FullState>(&json).unwrap().apply(&mut restored);
+
+        let snapshot = FullState::capture(&restored);
+        assert_near(Some(snapshot.idle_ms), 5000);
+        assert_near(snapshot.notification_shown_ms_ago, 2000);
+        assert_near(snapshot.wake_ack_remaining_ms, 3000);
+        assert_eq!(snapshot.text, "Cześć");
+        assert_eq!(snapshot.sensitivity_preview, Some(0.7));
+
+        let unset = FullState::capture(&OverlayState::new());
+        assert_eq!((unset.notification_shown_ms_ago, unset.wake_ack_remaining_ms), (None, None));
+    }
+}
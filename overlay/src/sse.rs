@@ -0,0 +1,168 @@
+// Server-sent events framing, shared by the status stream and the server log stream
+
+/// GET request for an event stream. Without these headers a server may answer with a
+/// plain JSON body, and a proxy may buffer or cache the stream. `last_event_id` is the last
+/// id from a previous connection, so the server can resume after it.
+pub fn request(client: &reqwest::Client, url: &str, last_event_id: Option<&str>) -> reqwest::RequestBuilder {
+    let request = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .header(reqwest::header::CACHE_CONTROL, "no-cache");
+    match last_event_id {
+        Some(id) if !id.is_empty() => request.header("Last-Event-ID", id),
+        _ => request,
+    }
+}
+
+/// Whether a response with this Content-Type header is an event stream.
+pub fn is_event_stream(content_type: Option<&str>) -> bool {
+    content_type
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("text/event-stream"))
+}
+
+/// One complete event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event:` field, "message" if the server didn't set one
+    pub event: String,
+    /// All `data:` lines, joined with "\n"
+    pub data: String,
+    /// Last `id:` seen on the stream up to this event (ids carry over to later events)
+    pub id: Option<String>,
+}
+
+/// Collects stream chunks and returns each complete event.
+///
+/// Chunks may split events, lines and even UTF-8 characters anywhere. Events without data
+/// (comments, keep-alives) are skipped, but an `id:` in them still counts.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    last_event_id: Option<String>,
+}
+
+impl SseParser {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        // CRLF line endings are allowed by the spec, drop the CRs so events end in "\n\n"
+        self.buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(event) = self.parse_event(&String::from_utf8_lossy(&block[..end])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn parse_event(&mut self, block: &str) -> Option<SseEvent> {
+        let mut event_type = None;
+        let mut data: Vec<&str> = Vec::new();
+        for line in block.lines().filter(|line| !line.starts_with(':')) {
+            // A line without a colon is a field name with an empty value
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "data" => data.push(value),
+                "event" => event_type = Some(value),
+                // Ids containing NUL are ignored by the spec; an empty one resets the id
+                "id" if !value.contains('\0') => {
+                    self.last_event_id = (!value.is_empty()).then(|| value.to_string());
+                }
+                _ => {}
+            }
+        }
+        if data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            event: event_type.filter(|name| !name.is_empty()).unwrap_or("message").to_string(),
+            data: data.join("\n"),
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_split_across_chunks_are_joined() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"data: {\"text\": \"Cze").is_empty());
+        // "ś" split between two chunks
+        let split = "ść\"}\n\ndata: second\n\n".as_bytes();
+        assert!(parser.push(&split[..1]).is_empty());
+        let events: Vec<String> = parser.push(&split[1..]).into_iter().map(|event| event.data).collect();
+        assert_eq!(events, ["{\"text\": \"Cześć\"}", "second"]);
+    }
+
+    #[tokio::test]
+    async fn gzip_compressed_stream_keeps_multibyte_text() {
+        use flate2::{write::GzEncoder, Compression};
+        use futures_util::StreamExt;
+        use std::io::Write;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all("data: {\"text\": \"Zażółć gęślą jaźń\"}\n\ndata: 🎙️ słucham\n\n".as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/status/stream", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0u8; 1024];
+            let length = socket.read(&mut buffer).await.unwrap();
+            assert!(String::from_utf8_lossy(&buffer[..length]).to_lowercase().contains("accept-encoding: gzip"));
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Encoding: gzip\r\nConnection: close\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            // Small writes, so the decompressed chunks end in the middle of characters
+            for piece in body.chunks(5) {
+                socket.write_all(piece).await.unwrap();
+                socket.flush().await.unwrap();
+            }
+        });
+
+        let client = reqwest::Client::builder().gzip(true).build().unwrap();
+        let response = request(&client, &url, None).send().await.unwrap();
+        let mut stream = response.bytes_stream();
+        let mut parser = SseParser::default();
+        let mut events = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            events.extend(parser.push(&chunk.unwrap()).into_iter().map(|event| event.data));
+        }
+        assert_eq!(events, ["{\"text\": \"Zażółć gęślą jaźń\"}", "🎙️ słucham"]);
+    }
+
+    #[test]
+    fn only_event_stream_responses_are_streams() {
+        assert!(is_event_stream(Some("text/event-stream")));
+        assert!(is_event_stream(Some("Text/Event-Stream; charset=utf-8")));
+        // A server that ignored Accept and answered like /api/status
+        assert!(!is_event_stream(Some("application/json")));
+        assert!(!is_event_stream(Some("text/html; charset=utf-8")));
+        assert!(!is_event_stream(None));
+    }
+
+    #[test]
+    fn data_lines_are_joined_and_event_and_id_are_kept() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b": keep-alive\n\nevent: log\nid: 7\ndata:first line\r\ndata: second line\r\n\r\n");
+        assert_eq!(
+            events,
+            [SseEvent { event: "log".to_string(), data: "first line\nsecond line".to_string(), id: Some("7".to_string()) }]
+        );
+    }
+
+    #[test]
+    fn event_id_carries_over_until_reset() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b"data: {}\n\nid: 8\n\ndata\ndata: x\n\nid\ndata: y\n\n");
+        let summary: Vec<_> = events.iter().map(|event| (event.event.as_str(), event.data.as_str(), event.id.as_deref())).collect();
+        // An id-only block sets the id without an event; "data" alone is an empty line
+        assert_eq!(summary, [("message", "{}", None), ("message", "\nx", Some("8")), ("message", "y", None)]);
+    }
+}
@@ -0,0 +1,492 @@
+// Structured assistant status, independent of the language the server uses for display text
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Idle,
+    Listening,
+    Thinking,
+    Speaking,
+    Notification,
+    Response,
+}
+
+// Legacy status text fragments (Polish and English), checked only when the server
+// doesn't send a machine-readable `state` field
+const LEGACY_THINKING: &[&str] = &["przetwarzam", "myślę", "thinking", "processing"];
+const LEGACY_SPEAKING: &[&str] = &["mówię", "speaking"];
+const LEGACY_LISTENING: &[&str] = &["słucham", "listening"];
+const LEGACY_NOTIFICATION: &[&str] = &["notification:", "powiadomienie:"];
+
+impl Status {
+    /// Derives the state from the legacy flags and (possibly localized) status text.
+    pub fn from_legacy(status_text: &str, text: &str, is_listening: bool, is_speaking: bool, wake_word_detected: bool) -> Status {
+        let status_lower = status_text.to_lowercase();
+        let text_lower = text.to_lowercase();
+        let matches = |fragments: &[&str]| {
+            fragments
+                .iter()
+                .any(|f| status_lower.contains(f) || text_lower.starts_with(f))
+        };
+
+        if is_speaking || matches(LEGACY_SPEAKING) {
+            Status::Speaking
+        } else if is_listening || wake_word_detected || matches(LEGACY_LISTENING) {
+            Status::Listening
+        } else if matches(LEGACY_THINKING) {
+            Status::Thinking
+        } else if matches(LEGACY_NOTIFICATION) {
+            Status::Notification
+        } else if !text.is_empty() {
+            Status::Response
+        } else {
+            Status::Idle
+        }
+    }
+
+    /// Whether the overlay should be visible in this state
+    pub fn shows_overlay(self) -> bool {
+        self != Status::Idle
+    }
+
+    /// Live interaction states, which are shown immediately even over queued notifications
+    pub fn is_live(self) -> bool {
+        matches!(self, Status::Listening | Status::Speaking)
+    }
+}
+
+/// Status payload sent by the client on `/api/status` and the SSE stream. Every field is
+/// optional and unknown fields are ignored, but a field with the wrong type rejects the payload.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StatusPayload {
+    /// Machine-readable state; unknown values fall back to legacy detection
+    pub state: Option<String>,
+    /// Status text, possibly localized
+    pub status: Option<String>,
+    pub text: Option<String>,
+    /// Older name for `text`, used if `text` is missing or empty
+    pub message: Option<String>,
+    /// True while `text` is an incremental chunk of a response that is still streaming
+    pub partial: Option<bool>,
+    pub is_listening: Option<bool>,
+    pub is_speaking: Option<bool>,
+    pub wake_word_detected: Option<bool>,
+    /// Wake word detection score (0.0-1.0), sent while sensitivity tuning is on
+    pub wake_confidence: Option<f64>,
+    /// One-shot action for the overlay to run (see `actions::Action`)
+    pub action: Option<String>,
+    /// Link for the "open_url" action
+    pub url: Option<String>,
+    /// Title and body for the "notify" action
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+impl StatusPayload {
+    pub fn parse(data: &serde_json::Value) -> Result<StatusPayload, String> {
+        serde_json::from_value(data.clone()).map_err(|e| format!("Malformed status payload: {}", e))
+    }
+
+    /// `text`, or the trimmed `message` for servers that send that instead.
+    pub fn display_text(&self) -> &str {
+        match self.text.as_deref() {
+            Some(text) if !text.is_empty() => text,
+            _ => self.message.as_deref().map(str::trim).unwrap_or(""),
+        }
+    }
+}
+
+/// The parts of a status payload that decide what the overlay shows.
+#[derive(Debug, Clone, Default)]
+pub struct StatusFields<'a> {
+    /// Machine-readable `state`, None if missing or unknown
+    pub state: Option<Status>,
+    pub status_text: &'a str,
+    pub text: &'a str,
+    pub is_listening: bool,
+    pub is_speaking: bool,
+    pub wake_word_detected: bool,
+}
+
+impl<'a> StatusFields<'a> {
+    pub fn from_payload(payload: &'a StatusPayload) -> StatusFields<'a> {
+        let state = payload.state.as_deref().and_then(|state| {
+            match serde_json::from_value::<Status>(serde_json::Value::from(state)) {
+                Ok(status) => Some(status),
+                Err(_) => {
+                    eprintln!("[Rust] Unknown status state '{}', using legacy detection", state);
+                    None
+                }
+            }
+        });
+        StatusFields {
+            state,
+            status_text: payload.status.as_deref().unwrap_or(""),
+            text: payload.display_text(),
+            is_listening: payload.is_listening.unwrap_or(false),
+            is_speaking: payload.is_speaking.unwrap_or(false),
+            wake_word_detected: payload.wake_word_detected.unwrap_or(false),
+        }
+    }
+}
+
+/// What the overlay should display for a payload, and whether it should be visible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShouldShow {
+    pub state: Status,
+    pub visible: bool,
+}
+
+/// Minimum text length, in characters (not bytes), before text brings up the overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinDisplayChars {
+    /// Text of payloads with `state: "response"`
+    pub response: usize,
+    /// Text of payloads without a `state`, where only the legacy detection says it is a response
+    pub raw_text: usize,
+}
+
+/// Visibility decision: the `state` field wins, otherwise the legacy flags and text decide.
+/// Text shorter than its threshold in `min_chars` isn't shown; notifications always are.
+pub fn should_show(fields: &StatusFields, min_chars: MinDisplayChars) -> ShouldShow {
+    let min_text_chars = if fields.state.is_some() { min_chars.response } else { min_chars.raw_text };
+    let state = fields.state.unwrap_or_else(|| {
+        Status::from_legacy(
+            fields.status_text,
+            fields.text,
+            fields.is_listening,
+            fields.is_speaking,
+            fields.wake_word_detected,
+        )
+    });
+    let visible = match state {
+        Status::Response => fields.text.chars().count() >= min_text_chars,
+        _ => state.shows_overlay(),
+    };
+    ShouldShow { state, visible }
+}
+
+/// Reasons the overlay may be kept hidden, and the settings that let live states through anyway.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Suppression {
+    pub user_disabled: bool,
+    pub show_live_when_disabled: bool,
+    pub dnd_active: bool,
+    pub snoozed: bool,
+    pub live_during_snooze: bool,
+}
+
+/// Whether the window may be shown for `decision` while the user has turned the overlay off,
+/// during do-not-disturb or while snoozed. Only live states get through: while disabled only
+/// if `show_live_when_disabled` is set, during do-not-disturb always, while snoozed only if
+/// `live_during_snooze` is set.
+pub fn may_show(decision: ShouldShow, suppression: Suppression) -> bool {
+    let live = decision.state.is_live();
+    decision.visible
+        && if suppression.user_disabled {
+            suppression.show_live_when_disabled && live
+        } else {
+            !suppression.dnd_active || live
+        }
+        && (!suppression.snoozed || (suppression.live_during_snooze && live))
+}
+
+// Overlay labels per language. To add a language, add a row; states without a label
+// (and unknown languages) show the server's raw status text.
+const DISPLAY_LABELS: &[(&str, &[(Status, &str)])] = &[
+    ("pl", &[(Status::Listening, "Słucham..."), (Status::Thinking, "Myślę..."), (Status::Speaking, "Mówię...")]),
+    ("en", &[(Status::Listening, "Listening..."), (Status::Thinking, "Thinking..."), (Status::Speaking, "Speaking...")]),
+];
+
+/// Base language codes that have overlay labels.
+pub fn supported_languages() -> Vec<&'static str> {
+    DISPLAY_LABELS.iter().map(|(code, _)| *code).collect()
+}
+
+/// Whether there are overlay labels for `language` ("pl", "en-US", ...).
+pub fn is_supported_language(language: &str) -> bool {
+    let language = base_language(language);
+    DISPLAY_LABELS.iter().any(|(code, _)| *code == language)
+}
+
+fn base_language(language: &str) -> String {
+    language.split(['-', '_']).next().unwrap_or("").to_lowercase()
+}
+
+/// Localized label for `state` in `language` ("pl", "en-US", ...), or `raw_status` if there is none.
+pub fn display_status(state: Status, language: &str, raw_status: &str) -> String {
+    let language = base_language(language);
+    DISPLAY_LABELS
+        .iter()
+        .find(|(code, _)| *code == language)
+        .and_then(|(_, labels)| labels.iter().find(|(s, _)| *s == state))
+        .map(|(_, label)| label.to_string())
+        .unwrap_or_else(|| raw_status.to_string())
+}
+
+// Longest excerpt of an unexpected /api/status body that is kept for diagnostics
+const BODY_SNIPPET_CHARS: usize = 200;
+
+/// A port answered /api/status, but not with the client's JSON (e.g. a proxy error page).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnexpectedResponse {
+    pub url: String,
+    pub status: u16,
+    pub body_snippet: String,
+    /// Where the request ended up if it was redirected (redirects are followed)
+    #[serde(default)]
+    pub redirected_to: Option<String>,
+    /// 401 or 403: something is there but wants credentials
+    #[serde(default)]
+    pub auth_required: bool,
+}
+
+impl UnexpectedResponse {
+    pub fn new(url: String, status: u16, body: &str, final_url: &str) -> Self {
+        UnexpectedResponse {
+            redirected_to: (final_url != url).then(|| final_url.to_string()),
+            url,
+            status,
+            body_snippet: body_snippet(body),
+            auth_required: matches!(status, 401 | 403),
+        }
+    }
+
+    /// One line for logs and last_error.
+    pub fn describe(&self) -> String {
+        let redirect = match &self.redirected_to {
+            Some(target) => format!(" (redirected to {})", target),
+            None => String::new(),
+        };
+        if self.auth_required {
+            format!("{}{} requires authentication (status {})", self.url, redirect, self.status)
+        } else {
+            format!(
+                "{}{} answered with status {} but is not the Gaja client: {}",
+                self.url, redirect, self.status, self.body_snippet
+            )
+        }
+    }
+}
+
+/// Start of `body` on one line, whitespace collapsed and cut at `BODY_SNIPPET_CHARS`.
+pub fn body_snippet(body: &str) -> String {
+    let collapsed = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(BODY_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+/// `text` cut to at most `max_chars` characters including a closing "…", or None if it fits.
+pub fn truncate_text(text: &str, max_chars: usize) -> Option<String> {
+    text.char_indices().nth(max_chars)?;
+    let end = text.char_indices().nth(max_chars.saturating_sub(1)).map_or(text.len(), |(end, _)| end);
+    Some(format!("{}…", text[..end].trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unexpected_responses_name_auth_and_redirects() {
+        let url = "http://127.0.0.1:5000/api/status".to_string();
+        let denied = UnexpectedResponse::new(url.clone(), 401, "Unauthorized", &url);
+        assert!(denied.auth_required);
+        assert_eq!(denied.describe(), "http://127.0.0.1:5000/api/status requires authentication (status 401)");
+
+        let login = UnexpectedResponse::new(url.clone(), 200, "<html>Sign in</html>", "http://127.0.0.1:5000/login");
+        assert!(!login.auth_required);
+        assert_eq!(login.redirected_to.as_deref(), Some("http://127.0.0.1:5000/login"));
+        assert!(login.describe().contains("(redirected to http://127.0.0.1:5000/login) answered with status 200"));
+    }
+
+    const ANY_LENGTH: MinDisplayChars = MinDisplayChars { response: 1, raw_text: 1 };
+
+    fn legacy(status_text: &'static str, text: &'static str, listening: bool, speaking: bool, wake_word: bool) -> StatusFields<'static> {
+        StatusFields {
+            state: None,
+            status_text,
+            text,
+            is_listening: listening,
+            is_speaking: speaking,
+            wake_word_detected: wake_word,
+        }
+    }
+
+    #[test]
+    fn legacy_fields_decide_state_and_visibility() {
+        let cases = [
+            // (fields, expected state, expected visible)
+            (legacy("", "", false, false, false), Status::Idle, false),
+            (legacy("Ready", "", false, false, false), Status::Idle, false),
+            (legacy("", "", false, false, true), Status::Listening, true),
+            (legacy("", "", true, false, false), Status::Listening, true),
+            (legacy("", "", false, true, false), Status::Speaking, true),
+            // Speaking wins over listening and the wake word
+            (legacy("", "", true, true, true), Status::Speaking, true),
+            (legacy("Słucham...", "", false, false, false), Status::Listening, true),
+            (legacy("Listening...", "", false, false, false), Status::Listening, true),
+            (legacy("Myślę...", "", false, false, false), Status::Thinking, true),
+            (legacy("Processing", "", false, false, false), Status::Thinking, true),
+            (legacy("Mówię", "Cześć!", false, false, false), Status::Speaking, true),
+            (legacy("", "Notification: timer done", false, false, false), Status::Notification, true),
+            (legacy("", "Powiadomienie: przypomnienie", false, false, false), Status::Notification, true),
+            (legacy("Ready", "Jutro będzie słonecznie.", false, false, false), Status::Response, true),
+        ];
+        for (fields, state, visible) in cases {
+            assert_eq!(should_show(&fields, ANY_LENGTH), ShouldShow { state, visible }, "{:?}", fields);
+        }
+    }
+
+    #[test]
+    fn state_field_overrides_legacy_fields() {
+        let mut fields = legacy("Mówię", "some text", true, true, true);
+        fields.state = Some(Status::Idle);
+        assert_eq!(should_show(&fields, ANY_LENGTH), ShouldShow { state: Status::Idle, visible: false });
+
+        let mut fields = legacy("", "", false, false, false);
+        fields.state = Some(Status::Thinking);
+        assert_eq!(should_show(&fields, ANY_LENGTH), ShouldShow { state: Status::Thinking, visible: true });
+    }
+
+    #[test]
+    fn min_display_chars_counts_characters_not_bytes() {
+        // "żółć" is 4 characters but 8 bytes
+        assert_eq!("żółć".len(), 8);
+        let short = legacy("", "tak", false, false, false);
+        let multibyte = legacy("", "żółć", false, false, false);
+        let long = legacy("", "Zażółć gęślą jaźń", false, false, false);
+        let at_least = |raw_text| MinDisplayChars { response: 1, raw_text };
+
+        assert!(!should_show(&short, at_least(5)).visible);
+        assert!(!should_show(&multibyte, at_least(5)).visible);
+        assert!(should_show(&long, at_least(5)).visible);
+        assert!(should_show(&multibyte, at_least(4)).visible);
+        // The threshold only applies to response text
+        assert!(should_show(&legacy("", "tak", false, true, false), at_least(5)).visible);
+    }
+
+    #[test]
+    fn min_display_chars_depends_on_the_payload_type() {
+        let min_chars = MinDisplayChars { response: 2, raw_text: 10 };
+        let mut response = legacy("", "OK", false, false, false);
+        response.state = Some(Status::Response);
+        assert!(should_show(&response, min_chars).visible);
+        // The same text without a state is only a guess at a response
+        assert!(!should_show(&legacy("", "OK", false, false, false), min_chars).visible);
+
+        // Notifications show however short they are
+        let mut notification = legacy("", "!", false, false, false);
+        notification.state = Some(Status::Notification);
+        let min_chars = MinDisplayChars { response: 50, raw_text: 50 };
+        assert!(should_show(&notification, min_chars).visible);
+        assert!(should_show(&legacy("", "Notification: !", false, false, false), min_chars).visible);
+    }
+
+    #[test]
+    fn disabled_overlay_stays_hidden() {
+        let speaking = should_show(&legacy("", "", false, true, false), ANY_LENGTH);
+        let response = should_show(&legacy("", "Jutro będzie słonecznie.", false, false, false), ANY_LENGTH);
+        assert!(speaking.visible && response.visible);
+
+        let disabled = Suppression { user_disabled: true, ..Default::default() };
+        assert!(may_show(speaking, Suppression::default()));
+        assert!(!may_show(speaking, disabled));
+        assert!(!may_show(response, disabled));
+        // The override only lets live states through
+        let live_allowed = Suppression { show_live_when_disabled: true, ..disabled };
+        assert!(may_show(speaking, live_allowed));
+        assert!(!may_show(response, live_allowed));
+    }
+
+    #[test]
+    fn do_not_disturb_only_lets_live_states_through() {
+        let speaking = should_show(&legacy("", "", false, true, false), ANY_LENGTH);
+        let notification = should_show(&legacy("", "Notification: timer done", false, false, false), ANY_LENGTH);
+        let dnd = Suppression { dnd_active: true, ..Default::default() };
+        assert!(may_show(speaking, dnd));
+        assert!(!may_show(notification, dnd));
+        assert!(may_show(notification, Suppression::default()));
+        // Disabling the overlay still wins
+        assert!(!may_show(speaking, Suppression { user_disabled: true, ..dnd }));
+    }
+
+    #[test]
+    fn snooze_hides_everything_unless_live_states_may_break_through() {
+        let speaking = should_show(&legacy("", "", false, true, false), ANY_LENGTH);
+        let response = should_show(&legacy("", "Jutro będzie słonecznie.", false, false, false), ANY_LENGTH);
+        let snoozed = Suppression { snoozed: true, ..Default::default() };
+        let breaking_through = Suppression { live_during_snooze: true, ..snoozed };
+        assert!(!may_show(speaking, snoozed));
+        assert!(may_show(speaking, breaking_through));
+        assert!(!may_show(response, breaking_through));
+        // Breaking through a snooze doesn't override disabling the overlay
+        assert!(!may_show(speaking, Suppression { user_disabled: true, ..breaking_through }));
+    }
+
+    #[test]
+    fn unknown_state_falls_back_to_legacy_detection() {
+        let data = serde_json::json!({"state": "dancing", "status": "", "text": "", "is_speaking": true});
+        let payload = StatusPayload::parse(&data).unwrap();
+        let fields = StatusFields::from_payload(&payload);
+        assert_eq!(fields.state, None);
+        assert_eq!(should_show(&fields, ANY_LENGTH).state, Status::Speaking);
+    }
+
+    #[test]
+    fn message_is_used_when_text_is_missing() {
+        let payload = StatusPayload::parse(&serde_json::json!({"message": "  Dzień dobry\n"})).unwrap();
+        let fields = StatusFields::from_payload(&payload);
+        assert_eq!(fields.text, "Dzień dobry");
+        assert_eq!(should_show(&fields, ANY_LENGTH), ShouldShow { state: Status::Response, visible: true });
+
+        let both = StatusPayload::parse(&serde_json::json!({"text": "", "message": "fallback"})).unwrap();
+        assert_eq!(both.display_text(), "fallback");
+        let both = StatusPayload::parse(&serde_json::json!({"text": "main", "message": "fallback"})).unwrap();
+        assert_eq!(both.display_text(), "main");
+    }
+
+    #[test]
+    fn payload_ignores_unknown_fields_and_rejects_wrong_types() {
+        let payload = StatusPayload::parse(&serde_json::json!({"text": "Cześć", "is_speaking": null, "volume": 3})).unwrap();
+        assert_eq!(payload.text.as_deref(), Some("Cześć"));
+        assert_eq!(payload.is_speaking, None);
+
+        assert!(StatusPayload::parse(&serde_json::json!({"is_listening": "yes"})).is_err());
+        assert!(StatusPayload::parse(&serde_json::json!({"text": 42})).is_err());
+        assert!(StatusPayload::parse(&serde_json::json!("listening")).is_err());
+    }
+
+    #[test]
+    fn languages_are_matched_without_the_region() {
+        for language in ["pl", "pl-PL", "en_US", "EN"] {
+            assert!(is_supported_language(language), "{}", language);
+        }
+        for language in ["de-DE", "", "english"] {
+            assert!(!is_supported_language(language), "{}", language);
+        }
+        assert_eq!(display_status(Status::Listening, "en-GB", "Słucham"), "Listening...");
+        assert_eq!(display_status(Status::Listening, "de", "Ich höre"), "Ich höre");
+    }
+
+    #[test]
+    fn body_snippet_is_one_short_line() {
+        assert_eq!(body_snippet("<html>\n  <body>502 Bad   Gateway</body>\n</html>\n"), "<html> <body>502 Bad Gateway</body> </html>");
+        assert_eq!(body_snippet(""), "");
+        let long = "ż".repeat(500);
+        assert_eq!(body_snippet(&long), format!("{}...", "ż".repeat(200)));
+    }
+
+    #[test]
+    fn long_text_is_cut_on_a_character_boundary() {
+        assert_eq!(truncate_text("Cześć", 5), None);
+        assert_eq!(truncate_text("Zażółć gęślą jaźń", 7).as_deref(), Some("Zażółć…"));
+        // Whitespace before the cut isn't kept in front of the ellipsis
+        assert_eq!(truncate_text("Zażółć gęślą jaźń", 8).as_deref(), Some("Zażółć…"));
+        assert_eq!(truncate_text("Zażółć gęślą jaźń", 9).as_deref(), Some("Zażółć g…"));
+    }
+}
@@ -0,0 +1,8 @@
+// Theme colors from Settings.theme as CSS variables, used by style.css and settings.css
+export const applyTheme = (theme) => {
+  if (!theme) return;
+  const root = document.documentElement.style;
+  root.setProperty("--theme-accent", theme.accent_color);
+  root.setProperty("--theme-background", theme.background_color);
+  root.setProperty("--theme-text", theme.text_color);
+};
@@ -0,0 +1,116 @@
+// Rate limiting for events emitted to the webview (keeps only the latest pending payload)
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Offer<T> {
+    /// Emit this payload now
+    EmitNow(T),
+    /// Payload stored; call `take_pending` after this delay
+    Schedule(Duration),
+    /// Payload replaced the one already waiting for a scheduled emit
+    Coalesced,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmitThrottle<T> {
+    min_interval: Duration,
+    last_emit: Option<Instant>,
+    pending: Option<T>,
+}
+
+impl<T> EmitThrottle<T> {
+    pub fn new(min_interval: Duration) -> Self {
+        EmitThrottle {
+            min_interval,
+            last_emit: None,
+            pending: None,
+        }
+    }
+
+    /// Critical payloads are always emitted immediately; others at most once per interval.
+    pub fn offer(&mut self, payload: T, now: Instant, critical: bool) -> Offer<T> {
+        let due = self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= self.min_interval);
+        if critical || (due && self.pending.is_none()) {
+            // Anything pending is older than this payload, so it's dropped
+            self.pending = None;
+            self.last_emit = Some(now);
+            return Offer::EmitNow(payload);
+        }
+
+        let was_pending = self.pending.replace(payload).is_some();
+        if was_pending {
+            Offer::Coalesced
+        } else {
+            let elapsed = self.last_emit.map_or(Duration::ZERO, |last| now.duration_since(last));
+            Offer::Schedule(self.min_interval.saturating_sub(elapsed))
+        }
+    }
+
+    /// Returns the latest pending payload, if any, and counts it as emitted.
+    pub fn take_pending(&mut self, now: Instant) -> Option<T> {
+        let pending = self.pending.take();
+        if pending.is_some() {
+            self.last_emit = Some(now);
+        }
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Feeds `count` payloads `spacing` apart and fires scheduled emits on time,
+    // returning the emitted payloads in order
+    fn simulate(count: u32, spacing: Duration, critical: impl Fn(u32) -> bool) -> Vec<u32> {
+        let mut throttle = EmitThrottle::new(Duration::from_millis(50));
+        let start = Instant::now();
+        let mut emitted = Vec::new();
+        let mut scheduled_at: Option<Instant> = None;
+
+        for i in 0..count {
+            let now = start + spacing * i;
+            if let Some(at) = scheduled_at.filter(|at| *at <= now) {
+                emitted.extend(throttle.take_pending(at));
+                scheduled_at = None;
+            }
+            match throttle.offer(i, now, critical(i)) {
+                Offer::EmitNow(payload) => emitted.push(payload),
+                Offer::Schedule(delay) => scheduled_at = Some(now + delay),
+                Offer::Coalesced => {}
+            }
+        }
+        if let Some(at) = scheduled_at {
+            emitted.extend(throttle.take_pending(at));
+        }
+        emitted
+    }
+
+    #[test]
+    fn burst_is_limited_to_twenty_per_second() {
+        // 200 identical-rate updates over one second (every 5ms)
+        let emitted = simulate(200, Duration::from_millis(5), |_| false);
+        assert!(emitted.len() <= 21, "emitted {} events", emitted.len());
+        assert!(emitted.len() >= 19, "emitted {} events", emitted.len());
+        // The final state is never lost
+        assert_eq!(emitted.last(), Some(&199));
+    }
+
+    #[test]
+    fn critical_updates_are_never_delayed() {
+        let emitted = simulate(100, Duration::from_millis(5), |i| i % 10 == 0);
+        for i in (0..100).step_by(10) {
+            assert!(emitted.contains(&i), "critical update {} was not emitted", i);
+        }
+        assert_eq!(emitted.last(), Some(&99));
+    }
+
+    #[test]
+    fn slow_updates_pass_straight_through() {
+        let emitted = simulate(10, Duration::from_millis(100), |_| false);
+        assert_eq!(emitted, (0..10).collect::<Vec<_>>());
+    }
+}
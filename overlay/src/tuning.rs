@@ -0,0 +1,64 @@
+// Wake word sensitivity tuning (start_sensitivity_tuning). Tracks whether the client was put
+// in tuning mode and the trial sensitivity, so tuning can be ended when nobody is watching.
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tuning {
+    active: bool,
+    // Trial sensitivity sent to the client, not saved
+    preview: Option<f64>,
+}
+
+impl Tuning {
+    /// State restored from a snapshot; a preview means the client was tuning.
+    pub fn restored(preview: Option<f64>) -> Self {
+        Tuning { active: preview.is_some(), preview }
+    }
+
+    pub fn preview(&self) -> Option<f64> {
+        self.preview
+    }
+
+    /// The sensitivity in effect: the preview while there is one, else `saved`.
+    pub fn sensitivity(&self, saved: f64) -> f64 {
+        self.preview.unwrap_or(saved)
+    }
+
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn set_preview(&mut self, value: f64) {
+        self.active = true;
+        self.preview = Some(value);
+    }
+
+    /// Ends tuning, returning whether it was on and the client has to be told.
+    pub fn stop(&mut self) -> bool {
+        self.preview = None;
+        std::mem::take(&mut self.active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopping_only_reports_tuning_that_was_on() {
+        let mut tuning = Tuning::default();
+        assert!(!tuning.stop());
+
+        tuning.start();
+        assert_eq!(tuning.sensitivity(0.5), 0.5);
+        tuning.set_preview(0.7);
+        assert_eq!(tuning.sensitivity(0.5), 0.7);
+        assert!(tuning.stop());
+        assert_eq!(tuning.preview(), None);
+        // Closing the settings window after an explicit stop has nothing left to undo
+        assert!(!tuning.stop());
+
+        let mut restored = Tuning::restored(Some(0.3));
+        assert!(restored.stop());
+        assert!(!Tuning::restored(None).stop());
+    }
+}
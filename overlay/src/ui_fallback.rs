@@ -0,0 +1,94 @@
+// Error page for a webview whose UI never started (missing asset, wrong path after install).
+// Tauri 1 reports page loads but not load errors, so each page has to report itself ready
+// with frontend_ready; one that doesn't within a timeout gets this page injected. Otherwise
+// the transparent, click-through overlay would just be invisible.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+struct PageLoad {
+    generation: u64,
+    ready: bool,
+}
+
+/// Page loads per window label, and whether the UI of the latest one reported ready.
+#[derive(Debug, Default)]
+pub struct PageLoads {
+    pages: Mutex<HashMap<String, PageLoad>>,
+}
+
+impl PageLoads {
+    /// Records a new page load in `label`, returning its generation for `is_stuck`.
+    pub fn loaded(&self, label: &str) -> u64 {
+        let mut pages = self.pages.lock().unwrap();
+        let page = pages.entry(label.to_string()).or_default();
+        page.generation += 1;
+        page.ready = false;
+        page.generation
+    }
+
+    pub fn mark_ready(&self, label: &str) {
+        if let Some(page) = self.pages.lock().unwrap().get_mut(label) {
+            page.ready = true;
+        }
+    }
+
+    /// True if load `generation` is still the latest in `label` and never became ready.
+    pub fn is_stuck(&self, label: &str, generation: u64) -> bool {
+        self.pages
+            .lock()
+            .unwrap()
+            .get(label)
+            .is_some_and(|page| page.generation == generation && !page.ready)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Script for `Window::eval` that replaces the page with an opaque error message.
+pub fn fallback_script(url: &str) -> String {
+    let html = format!(
+        "<head><title>Gaja Overlay</title></head>\
+         <body style=\"margin:0;padding:24px;background:#fff;color:#2e2e2e;font:16px sans-serif\">\
+         <h2>Failed to load overlay UI</h2>\
+         <p>Tried to load <code>{}</code>, but the page didn't start.</p>\
+         <p>Reinstalling the overlay usually fixes missing files.</p></body>",
+        escape_html(url)
+    );
+    // A JSON string is also a valid JavaScript string literal
+    format!("document.documentElement.innerHTML = {};", serde_json::Value::from(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_latest_load_can_be_stuck() {
+        let pages = PageLoads::default();
+        let first = pages.loaded("settings");
+        assert!(pages.is_stuck("settings", first));
+        // Reloaded before the timeout; the old load's check must not fire
+        let second = pages.loaded("settings");
+        assert!(!pages.is_stuck("settings", first));
+        pages.mark_ready("settings");
+        assert!(!pages.is_stuck("settings", second));
+        assert!(!pages.is_stuck("main", 1));
+    }
+
+    #[test]
+    fn url_is_escaped_in_the_error_page() {
+        let script = fallback_script("tauri://localhost/<img src=x onerror=alert(1)>\"');");
+        assert!(script.contains("&lt;img src=x onerror=alert(1)&gt;"));
+        assert!(!script.contains("<img"));
+        let literal = script.strip_prefix("document.documentElement.innerHTML = ").unwrap().strip_suffix(';').unwrap();
+        assert!(serde_json::from_str::<String>(literal).unwrap().contains("Failed to load overlay UI"));
+    }
+}
@@ -22,5 +22,12 @@ export default defineConfig(async () => ({
     // Output directory relative to the project root (f:\\Asystent\\overlay\\)
     outDir: "../dist",
     emptyOutDir: true,
+    rollupOptions: {
+      // Overlay page plus the settings window opened from the tray
+      input: {
+        main: path.resolve(__dirname, "src/index.html"),
+        settings: path.resolve(__dirname, "src/settings.html"),
+      },
+    },
   },
 }));